@@ -0,0 +1,60 @@
+use anyhow::{anyhow, bail, Result};
+
+const USAGE: &str = "Usage:
+    arxivfeed                     Build the site
+    arxivfeed script test [OPTIONS]
+                                  Run script helpers against fixture papers and `*_test.rhai` files
+
+Options for `script test`:
+    --fixtures <FILE>   JSON list of papers or a cache.json [default: <target_dir>/cache.json]
+    --field <NAME>      Pass this paper field to helpers instead of the whole paper
+    --script <NAME>     Only exercise the named helper
+    --limit <N>         Only use the first N fixture papers";
+
+/// What to do in this run.
+#[derive(Debug)]
+pub enum Command {
+    Build,
+    ScriptTest(ScriptTestArgs),
+}
+
+#[derive(Debug, Default)]
+pub struct ScriptTestArgs {
+    pub fixtures: Option<String>,
+    pub field: Option<String>,
+    pub script: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl Command {
+    pub fn from_args() -> Result<Command> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            [] => Ok(Command::Build),
+            ["script", "test", ..] => Ok(Command::ScriptTest(ScriptTestArgs::parse(&args[2..])?)),
+            _ => bail!("{}", USAGE),
+        }
+    }
+}
+
+impl ScriptTestArgs {
+    fn parse(args: &[String]) -> Result<ScriptTestArgs> {
+        let mut parsed = ScriptTestArgs::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing value for {}\n\n{}", flag, USAGE))
+            };
+            match flag.as_str() {
+                "--fixtures" => parsed.fixtures = Some(value()?),
+                "--field" => parsed.field = Some(value()?),
+                "--script" => parsed.script = Some(value()?),
+                "--limit" => parsed.limit = Some(value()?.parse()?),
+                _ => bail!("Unknown option {}\n\n{}", flag, USAGE),
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -1,15 +1,16 @@
 use crate::crate_name;
+use crate::utils::stable_hash;
+use anyhow::{bail, Result};
 use figment::{
-    error::Result,
     providers::Toml,
     providers::{Format, Serialized},
     Figment,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use tracing::info;
 
-
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Version {
     V1,
@@ -23,11 +24,729 @@ pub struct Source {
     pub category: String,
 }
 
+/// A script helper, given as a file path or as inline Rhai source.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Script {
+    Path(String),
+    Inline { source: String },
+}
+
+/// Resource limits applied to every Rhai script invocation.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScriptLimits {
+    pub max_operations: u64,
+    pub max_call_levels: usize,
+    pub max_expr_depth: usize,
+    pub max_string_size: usize,
+    pub max_array_size: usize,
+    pub max_map_size: usize,
+    pub timeout_ms: u64,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> ScriptLimits {
+        ScriptLimits {
+            max_operations: 10_000_000,
+            max_call_levels: 64,
+            max_expr_depth: 64,
+            max_string_size: 1 << 20,
+            max_array_size: 100_000,
+            max_map_size: 100_000,
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+/// A paper field that rules can match against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Title,
+    Summary,
+    Authors,
+    Comment,
+}
+
+impl Field {
+    pub fn all() -> Vec<Field> {
+        vec![Field::Title, Field::Summary, Field::Authors, Field::Comment]
+    }
+}
+
+/// A weighted keyword or regex rule, literal patterns match on word boundaries.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ScoreRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default = "ScoreRule::default_weight")]
+    pub weight: f64,
+    #[serde(default = "Field::all")]
+    pub fields: Vec<Field>,
+}
+
+impl ScoreRule {
+    fn default_weight() -> f64 {
+        1.0
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Scoring {
+    /// Order papers of each section by descending score.
+    pub sort: bool,
+    /// Drop papers scoring below this.
+    pub min_score: Option<f64>,
+    pub rules: Vec<ScoreRule>,
+}
+
+/// Terms wrapped in `<mark>` by the `mark` helper.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Highlight {
+    /// CSS class of `terms` and of the terms matched by scoring rules.
+    pub class: String,
+    pub terms: Vec<String>,
+    /// More term lists, keyed by CSS class.
+    pub classes: HashMap<String, Vec<String>>,
+    pub case_sensitive: bool,
+    /// Also highlight the `matched_terms` found by scoring.
+    pub matched_terms: bool,
+}
+
+impl Default for Highlight {
+    fn default() -> Highlight {
+        Highlight {
+            class: "highlight".to_string(),
+            terms: Default::default(),
+            classes: Default::default(),
+            case_sensitive: false,
+            matched_terms: true,
+        }
+    }
+}
+
+/// A watched author, papers by any of the name variants are pinned on top of each day.
+/// Names match regardless of order, accents and abbreviated given names.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WatchedAuthor {
+    pub name: String,
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// Identifiers kept for templates, the arXiv API only exposes author names.
+    pub orcid: Option<String>,
+    pub arxiv_id: Option<String>,
+    /// Also query arXiv for the latest `fetch` papers of this author, whatever their category.
+    #[serde(default)]
+    pub fetch: i32,
+}
+
+/// Papers hidden before rendering, patterns are case-insensitive regexes.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Mute {
+    /// arXiv IDs, a versionless ID mutes every version.
+    pub ids: Vec<String>,
+    /// A file of more IDs, one per line, `#` starts a comment.
+    pub ids_file: Option<String>,
+    pub authors: Vec<String>,
+    pub title: Vec<String>,
+    pub summary: Vec<String>,
+    pub comment: Vec<String>,
+}
+
+/// Links from each paper to the most similar papers of the collection, by TF-IDF of their
+/// titles and abstracts.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Related {
+    pub enabled: bool,
+    pub top_k: usize,
+    /// Cosine similarity, from 0 to 1, below which papers are not related.
+    pub min_similarity: f64,
+}
+
+impl Default for Related {
+    fn default() -> Related {
+        Related {
+            enabled: false,
+            top_k: 5,
+            min_similarity: 0.15,
+        }
+    }
+}
+
+/// Group the papers of each category into topics, by k-means on their TF-IDF vectors.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Clustering {
+    pub enabled: bool,
+    /// Categories with fewer papers stay a flat list.
+    pub min_papers: usize,
+    /// Roughly how many papers make a topic, which sets the number of topics.
+    pub papers_per_topic: usize,
+    pub max_topics: usize,
+    /// Number of top terms in a topic label.
+    pub label_terms: usize,
+    pub iterations: usize,
+}
+
+impl Default for Clustering {
+    fn default() -> Clustering {
+        Clustering {
+            enabled: false,
+            min_papers: 12,
+            papers_per_topic: 10,
+            max_topics: 8,
+            label_terms: 2,
+            iterations: 20,
+        }
+    }
+}
+
+/// A section of the papers closest to a profile learnt from liked papers.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Recommend {
+    /// A file of liked arXiv IDs, one per line, `#` starts a comment.
+    pub seeds_file: Option<String>,
+    /// Title of the section pinned on top of each day.
+    pub title: String,
+    /// Most papers recommended per day.
+    pub top_k: usize,
+    /// Cosine similarity, from 0 to 1, below which papers are not recommended.
+    pub min_similarity: f64,
+}
+
+impl Default for Recommend {
+    fn default() -> Recommend {
+        Recommend {
+            seeds_file: None,
+            title: "Recommended for you".to_string(),
+            top_k: 10,
+            min_similarity: 0.1,
+        }
+    }
+}
+
+/// A TL;DR of each paper from an OpenAI-compatible chat completions endpoint.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Tldr {
+    pub enabled: bool,
+    /// The API root, `/chat/completions` is appended.
+    pub base_url: String,
+    pub model: String,
+    /// Environment variable holding the API key, if the endpoint needs one.
+    pub api_key_env: Option<String>,
+    pub system: Option<String>,
+    /// Handlebars template of the user message, given the paper.
+    pub prompt: String,
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Most tokens of each TL;DR.
+    pub max_tokens: u32,
+    /// Most tokens spent by a build, prompts included. New requests stop once it is reached.
+    pub token_budget: Option<u64>,
+    pub timeout_secs: u64,
+}
+
+impl Default for Tldr {
+    fn default() -> Tldr {
+        Tldr {
+            enabled: false,
+            base_url: "http://localhost:8080/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: None,
+            system: Some(
+                "You summarize research papers in one plain sentence of at most 30 words."
+                    .to_string(),
+            ),
+            prompt: "Title: {{title}}\n\nAbstract: {{summary}}\n\nTL;DR:".to_string(),
+            concurrency: 4,
+            max_tokens: 80,
+            token_budget: None,
+            timeout_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslateBackend {
+    /// An OpenAI-compatible chat completions endpoint.
+    OpenAi,
+    /// A LibreTranslate-compatible `/translate` endpoint.
+    LibreTranslate,
+}
+
+/// Abstracts translated into each of `languages`, cached with the paper.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Translate {
+    /// Target language codes, e.g. `zh`. Translation is off when empty.
+    pub languages: Vec<String>,
+    pub backend: TranslateBackend,
+    pub base_url: String,
+    /// Chat model, for the `openai` backend.
+    pub model: String,
+    /// Environment variable holding the API key, if the endpoint needs one.
+    pub api_key_env: Option<String>,
+    /// Handlebars template of the chat message, given `lang`, `title` and `text`, for the
+    /// `openai` backend.
+    pub prompt: String,
+    pub concurrency: usize,
+    pub max_tokens: u32,
+    pub timeout_secs: u64,
+    /// Failures in a row after which the remaining abstracts wait for the next build, never when 0.
+    pub max_failures: usize,
+}
+
+impl Default for Translate {
+    fn default() -> Translate {
+        Translate {
+            languages: Default::default(),
+            backend: TranslateBackend::OpenAi,
+            base_url: "http://localhost:8080/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: None,
+            prompt: "Translate this abstract of the paper \"{{title}}\" into the language \
+                     `{{lang}}`. Keep LaTeX as is and reply with the translation only.\n\n{{text}}"
+                .to_string(),
+            concurrency: 4,
+            max_tokens: 1024,
+            timeout_secs: 120,
+            max_failures: 5,
+        }
+    }
+}
+
+/// An OpenAI-compatible embeddings endpoint, used by `[[semantic_sources]]`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Embeddings {
+    /// The API root, `/embeddings` is appended.
+    pub base_url: String,
+    pub model: String,
+    /// Environment variable holding the API key, if the endpoint needs one.
+    pub api_key_env: Option<String>,
+    /// Papers sent per request.
+    pub batch_size: usize,
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for Embeddings {
+    fn default() -> Embeddings {
+        Embeddings {
+            base_url: "http://localhost:8080/v1".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            api_key_env: None,
+            batch_size: 32,
+            concurrency: 2,
+            timeout_secs: 60,
+        }
+    }
+}
+
+/// A section of the papers of any source close to a topic description, by cosine similarity of
+/// their embeddings.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SemanticSource {
+    pub title: String,
+    pub description: String,
+    #[serde(default = "SemanticSource::default_threshold")]
+    pub threshold: f64,
+}
+
+impl SemanticSource {
+    fn default_threshold() -> f64 {
+        0.5
+    }
+}
+
+/// LaTeX math in titles and abstracts.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Math {
+    /// Render `$...$` and `$$...$$` math as MathML in the `mark` helper.
+    pub enabled: bool,
+}
+
+impl Default for Math {
+    fn default() -> Math {
+        Math { enabled: true }
+    }
+}
+
+/// HTML escaping of template output.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Html {
+    /// Escape `{{...}}` output; `{{{...}}}` and the `safe` helper write HTML as is.
+    pub escape: bool,
+    /// Tags kept by the sanitizer of `safe` and of script helper HTML, besides ammonia's defaults.
+    pub allowed_tags: Vec<String>,
+    /// Attributes kept on every tag, besides ammonia's defaults.
+    pub allowed_attributes: Vec<String>,
+}
+
+impl Default for Html {
+    fn default() -> Html {
+        Html {
+            escape: true,
+            allowed_tags: Default::default(),
+            allowed_attributes: vec!["class".to_string()],
+        }
+    }
+}
+
+/// Pages of the `V2` site.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Pages {
+    /// Number of days shown on the index page, the others are on their own pages.
+    pub index_days: usize,
+}
+
+impl Default for Pages {
+    fn default() -> Pages {
+        Pages { index_days: 1 }
+    }
+}
+
+/// An artifact written by the build; `[[outputs]]` lists several, all rendered from one fetch.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Output {
+    /// The single page `index.html`.
+    V1(SiteOutput),
+    /// A page per day, source and paper.
+    V2(SiteOutput),
+    /// RSS 2.0 and Atom feeds of every paper and of each source.
+    Feed(FeedOutput),
+    /// A JSON Feed and a static JSON API of days, sources and papers.
+    Json(JsonOutput),
+    /// A GitHub-flavoured Markdown digest, e.g. a `README.md`.
+    Markdown(MarkdownOutput),
+    /// A digest sent by email, or written as `.eml` files.
+    Email(EmailOutput),
+}
+
+/// Where an HTML output is written, by default `target_dir` and `target_name`, and where its
+/// templates are overridden.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SiteOutput {
+    /// By default `target_dir`, and `target_dir/v2` for a `v2` output of `[[outputs]]`.
+    pub target_dir: Option<String>,
+    pub target_name: Option<String>,
+    /// By default `templates_dir`, and `templates_dir/v2` for `v2`.
+    pub templates_dir: Option<String>,
+}
+
+/// Feeds written in `dir` of the target directory.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FeedOutput {
+    pub target_dir: Option<String>,
+    pub dir: String,
+    /// Newest papers kept in each feed.
+    pub max_items: usize,
+    pub rss: bool,
+    pub atom: bool,
+    /// Also write a feed per source.
+    pub per_source: bool,
+}
+
+impl Default for FeedOutput {
+    fn default() -> FeedOutput {
+        FeedOutput {
+            target_dir: None,
+            dir: "feeds".to_string(),
+            max_items: 100,
+            rss: true,
+            atom: true,
+            per_source: true,
+        }
+    }
+}
+
+/// JSON written in the target directory.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JsonOutput {
+    pub target_dir: Option<String>,
+    /// The JSON Feed file, none when empty.
+    pub feed: String,
+    /// Newest papers kept in the JSON Feed.
+    pub max_items: usize,
+    /// Directory of the API, none when empty.
+    pub api_dir: String,
+}
+
+impl Default for JsonOutput {
+    fn default() -> JsonOutput {
+        JsonOutput {
+            target_dir: None,
+            feed: "feed.json".to_string(),
+            max_items: 100,
+            api_dir: "api".to_string(),
+        }
+    }
+}
+
+/// How a Markdown digest lists the papers of a source.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownStyle {
+    List,
+    Table,
+}
+
+/// A Markdown digest written to `file` in the target directory.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MarkdownOutput {
+    pub target_dir: Option<String>,
+    pub file: String,
+    pub style: MarkdownStyle,
+    /// Newest days in the digest, every day when unset.
+    pub days: Option<usize>,
+    /// Templates overriding `digest`, `paper` and `table`.
+    pub templates_dir: String,
+}
+
+impl Default for MarkdownOutput {
+    fn default() -> MarkdownOutput {
+        MarkdownOutput {
+            target_dir: None,
+            file: "digest.md".to_string(),
+            style: MarkdownStyle::List,
+            days: None,
+            templates_dir: "includes/markdown".to_string(),
+        }
+    }
+}
+
+/// A multipart HTML and plain text digest of the newest `days`, sent over `smtp` to `to`, or
+/// written to `eml_dir` in the target directory when no server is set.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailOutput {
+    pub target_dir: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Handlebars template of the subject, given the digest.
+    pub subject: String,
+    /// Newest days in the digest, e.g. 1 for a daily and 7 for a weekly digest.
+    pub days: usize,
+    pub smtp: Option<Smtp>,
+    pub eml_dir: String,
+    /// Templates overriding `html` and `text`.
+    pub templates_dir: String,
+    /// The file keeping the newest date of the last digest, by default a ledger named after the
+    /// sender, recipients and server.
+    pub sent_file: Option<String>,
+}
+
+impl Default for EmailOutput {
+    fn default() -> EmailOutput {
+        EmailOutput {
+            target_dir: None,
+            from: format!("{} <{}@localhost>", crate_name!(), crate_name!()),
+            to: Default::default(),
+            subject: "{{site_title}}: {{dates}}".to_string(),
+            days: 1,
+            smtp: None,
+            eml_dir: "emails".to_string(),
+            templates_dir: "includes/email".to_string(),
+            sent_file: None,
+        }
+    }
+}
+
+/// An SMTP server, by default on the submission port with STARTTLS.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Smtp {
+    pub host: String,
+    /// Default is 587 for `starttls`, 465 for `tls` and 25 for `none`.
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    /// Environment variable holding the password of `username`.
+    pub password_env: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Plain text, e.g. for a local relay.
+    None,
+    #[default]
+    Starttls,
+    /// Implicit TLS.
+    Tls,
+}
+
+/// Which of the new papers are posted: papers of `sources`, or of every source when empty, and when
+/// `watched` or `min_score` is set, only papers by watched authors or scoring at least `min_score`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PaperFilter {
+    pub sources: Vec<String>,
+    pub watched: bool,
+    pub min_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatService {
+    /// A Slack incoming webhook.
+    Slack,
+    /// A Discord webhook.
+    Discord,
+    /// A Matrix room, posted to as the user of `token_env`.
+    Matrix,
+    /// Any endpoint taking a JSON `POST` of the message and its papers.
+    Generic,
+}
+
+/// A chat channel notified of the new papers of each build.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Notifier {
+    pub service: ChatService,
+    /// The webhook URL, or the homeserver URL for Matrix.
+    pub url: Option<String>,
+    /// Environment variable holding `url`, as webhook URLs are secrets.
+    pub url_env: Option<String>,
+    /// The Matrix room ID, e.g. `!abcdef:matrix.org`.
+    pub room: Option<String>,
+    /// Environment variable holding the Matrix access token, or a bearer token for `generic`.
+    pub token_env: Option<String>,
+    /// Newest days of papers posted.
+    #[serde(default = "Notifier::default_days")]
+    pub days: usize,
+    #[serde(flatten)]
+    pub filter: PaperFilter,
+    /// Handlebars template of the first line, given `site_title`, `base_url`, `dates` and `papers`.
+    pub header: Option<String>,
+    /// Handlebars template of each paper, given the paper and its `sources`.
+    pub template: Option<String>,
+    /// Characters per message, the limit of the service by default. Longer posts are split
+    /// between papers.
+    pub max_length: Option<usize>,
+    #[serde(default = "Notifier::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// File of the papers announced, so that they are never announced twice, by default
+    /// `ledgers/notified-<service>-<hash>.json` of `target_dir`, after the service, `url_env` or
+    /// `url`, and `room`.
+    pub ledger: Option<String>,
+}
+
+impl Notifier {
+    fn default_days() -> usize {
+        1
+    }
+
+    fn default_timeout_secs() -> u64 {
+        30
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BotService {
+    /// The statuses API of Mastodon and compatible servers.
+    Mastodon,
+    /// Bluesky posts, through the PDS of an AT Protocol account.
+    Atproto,
+}
+
+/// An account posting each new paper as a status, the papers of a day in a thread.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Bot {
+    pub service: BotService,
+    /// The Mastodon server, or the PDS of the account, e.g. `https://bsky.social`.
+    pub url: String,
+    /// The handle or DID of the AT Protocol account.
+    pub identifier: Option<String>,
+    /// Environment variable holding the Mastodon access token, or the AT Protocol app password.
+    pub token_env: String,
+    /// Newest days of papers posted.
+    #[serde(default = "Bot::default_days")]
+    pub days: usize,
+    #[serde(flatten)]
+    pub filter: PaperFilter,
+    /// Handlebars template of each status, given the paper and its `sources`. The link to the
+    /// paper is appended.
+    pub template: Option<String>,
+    /// Handlebars template of a status opening each day's thread, given `site_title`, `base_url`,
+    /// `date` and `papers`. The thread starts with the first paper when unset.
+    pub header: Option<String>,
+    /// Reply to the previous status of the day, so that each day is a thread.
+    #[serde(default = "Bot::default_thread")]
+    pub thread: bool,
+    /// Characters per status, 500 on Mastodon and 300 on Bluesky by default.
+    pub max_length: Option<usize>,
+    /// Statuses posted per build at most, the other papers wait for the next build.
+    #[serde(default = "Bot::default_max_posts")]
+    pub max_posts: usize,
+    /// Seconds between two statuses.
+    #[serde(default = "Bot::default_interval_secs")]
+    pub interval_secs: u64,
+    /// Visibility of Mastodon statuses: `public`, `unlisted`, `private` or `direct`.
+    #[serde(default = "Bot::default_visibility")]
+    pub visibility: String,
+    /// Language of the statuses, e.g. `en`.
+    pub language: Option<String>,
+    /// File of the papers posted, so that they are never posted twice, by default
+    /// `ledgers/posted-<service>-<hash>.json` of `target_dir`, after the service, `url`,
+    /// `identifier` and `token_env`.
+    pub ledger: Option<String>,
+    #[serde(default = "Bot::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Bot {
+    fn default_days() -> usize {
+        1
+    }
+
+    fn default_thread() -> bool {
+        true
+    }
+
+    fn default_max_posts() -> usize {
+        10
+    }
+
+    fn default_interval_secs() -> u64 {
+        5
+    }
+
+    fn default_visibility() -> String {
+        "public".to_string()
+    }
+
+    fn default_timeout_secs() -> u64 {
+        30
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub version: Version,
     pub limit_days: i64,
     pub site_title: String,
+    /// Public URL of `target_dir`, for the absolute links of feeds.
+    pub base_url: Option<String>,
     pub target_dir: String,
     pub statics_dir: String,
     pub templates_dir: String,
@@ -35,7 +754,32 @@ pub struct Config {
     pub cache_url: Option<String>,
     pub target_name: Option<String>,
     pub sources: Vec<Source>,
-    pub scripts: HashMap<String, String>,
+    pub scripts_dir: Option<String>,
+    pub scripts: HashMap<String, Script>,
+    pub script_limits: ScriptLimits,
+    pub scoring: Scoring,
+    pub highlight: Highlight,
+    pub watched_title: String,
+    pub authors: Vec<WatchedAuthor>,
+    /// Canonical author names and the other ways they are written.
+    pub author_aliases: HashMap<String, Vec<String>>,
+    pub mute: Mute,
+    pub related: Related,
+    pub clustering: Clustering,
+    pub recommend: Recommend,
+    pub tldr: Tldr,
+    pub translate: Translate,
+    pub embeddings: Embeddings,
+    pub semantic_sources: Vec<SemanticSource>,
+    pub math: Math,
+    pub html: Html,
+    pub pages: Pages,
+    /// Outputs of the build, only the one of `version` when empty.
+    pub outputs: Vec<Output>,
+    /// Chat channels notified of the new papers once the outputs are written.
+    pub notifiers: Vec<Notifier>,
+    /// Accounts posting the new papers once the outputs are written.
+    pub bots: Vec<Bot>,
 }
 
 impl Default for Config {
@@ -44,6 +788,7 @@ impl Default for Config {
             version: Version::V1,
             limit_days: 1,
             site_title: crate_name!().to_string(),
+            base_url: None,
             target_dir: "target".to_string(),
             statics_dir: "statics".to_string(),
             templates_dir: "includes".to_string(),
@@ -51,7 +796,28 @@ impl Default for Config {
             cache_url: None,
             target_name: None,
             sources: Default::default(),
+            scripts_dir: None,
             scripts: Default::default(),
+            script_limits: Default::default(),
+            scoring: Default::default(),
+            highlight: Default::default(),
+            watched_title: "Watched authors".to_string(),
+            authors: Default::default(),
+            author_aliases: Default::default(),
+            mute: Default::default(),
+            related: Default::default(),
+            clustering: Default::default(),
+            recommend: Default::default(),
+            tldr: Default::default(),
+            translate: Default::default(),
+            embeddings: Default::default(),
+            semantic_sources: Default::default(),
+            math: Default::default(),
+            html: Default::default(),
+            pages: Default::default(),
+            outputs: Default::default(),
+            notifiers: Default::default(),
+            bots: Default::default(),
         }
     }
 }
@@ -59,8 +825,39 @@ impl Default for Config {
 impl Config {
     pub fn new() -> Result<Config> {
         info!("Loading config!");
-        Figment::from(Serialized::defaults(Config::default()))
+        let config: Config = Figment::from(Serialized::defaults(Config::default()))
             .merge(Toml::file("config.toml"))
-            .extract()
+            .extract()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let kept = self.limit_days.max(1);
+        for output in &self.outputs {
+            if let Output::Email(email) = output {
+                if email.days as i64 > kept {
+                    bail!(
+                        "The email output to {} has `days = {}`, but only `limit_days = {}` days are kept",
+                        email.to.join(", "),
+                        email.days,
+                        kept
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The default path of a ledger, in `ledgers/` of `target_dir` next to `cache.json`, named
+    /// after its `kind` and a stable hash of what identifies its output or notifier, so that it
+    /// stays with them when others are added or reordered.
+    pub fn ledger_path(&self, kind: &str, identity: &[&str], extension: &str) -> String {
+        let name = format!("{}-{}.{}", kind, stable_hash(identity), extension);
+        Path::new(&self.target_dir)
+            .join("ledgers")
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
     }
 }
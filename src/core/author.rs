@@ -0,0 +1,298 @@
+use crate::text;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Lowercase name particles that belong to the family name, as in `Maria van der Berg`.
+const PARTICLES: &[&str] = &[
+    "al", "bin", "da", "das", "de", "del", "della", "den", "der", "di", "do", "dos", "du", "el",
+    "la", "le", "st", "te", "ten", "ter", "van", "von",
+];
+
+const SUFFIXES: &[&str] = &["jr", "sr", "ii", "iii", "iv"];
+
+/// A paper author, with the normalized forms used to match names written differently.
+///
+/// Deserializes from a plain name as well, which is how older caches store authors.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "AuthorRepr")]
+pub struct Author {
+    /// The name as given by arXiv, in Unicode NFKC.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affiliation: Option<String>,
+    /// The name after resolving aliases, the one to display.
+    pub canonical: String,
+    /// Lowercase, accent-free `given family` form, e.g. `john smith`.
+    pub normalized: String,
+    /// Family name and initials, e.g. `smith_j`.
+    pub key: String,
+    #[serde(skip)]
+    given: Vec<String>,
+    #[serde(skip)]
+    family: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AuthorRepr {
+    Name(String),
+    Full {
+        name: String,
+        affiliation: Option<String>,
+        canonical: Option<String>,
+    },
+}
+
+impl From<AuthorRepr> for Author {
+    fn from(repr: AuthorRepr) -> Author {
+        match repr {
+            AuthorRepr::Name(name) => Author::new(&name, None),
+            AuthorRepr::Full {
+                name,
+                affiliation,
+                canonical,
+            } => {
+                let mut author = Author::new(&name, affiliation);
+                if let Some(canonical) = canonical {
+                    author.set_canonical(&canonical);
+                }
+                author
+            }
+        }
+    }
+}
+
+impl Author {
+    pub fn new(name: &str, affiliation: Option<String>) -> Author {
+        let name = text::nfkc(name.trim());
+        let mut author = Author {
+            name: name.clone(),
+            affiliation,
+            canonical: Default::default(),
+            normalized: Default::default(),
+            key: Default::default(),
+            given: Default::default(),
+            family: Default::default(),
+        };
+        author.set_canonical(&name);
+        author
+    }
+
+    fn set_canonical(&mut self, canonical: &str) {
+        let (given, family) = split_name(canonical);
+        let initials: String = given.iter().filter_map(|g| g.chars().next()).collect();
+        self.canonical = canonical.to_string();
+        self.normalized = given
+            .iter()
+            .chain(std::iter::once(&family))
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.key = std::iter::once(family.replace([' ', '-'], "_"))
+            .chain((!initials.is_empty()).then_some(initials))
+            .collect::<Vec<_>>()
+            .join("_");
+        self.given = given;
+        self.family = family;
+    }
+
+    /// The arXiv search term for the author, `au:family_i` with the first initial, as arXiv
+    /// indexes names. Falls back to the quoted name when it has no family name.
+    pub fn search_query(&self) -> String {
+        if self.family.is_empty() {
+            return format!("au:\"{}\"", self.name);
+        }
+        let family = self.family.replace([' ', '-'], "_");
+        match self.given.first().and_then(|g| g.chars().next()) {
+            Some(initial) => format!("au:{}_{}", family, initial),
+            None => format!("au:{}", family),
+        }
+    }
+
+    /// Whether both may be the same person: same family name and as many given names, each
+    /// compatible with the one in the same place, an initial being compatible with any name it
+    /// starts.
+    pub fn matches(&self, other: &Author) -> bool {
+        if self.family.is_empty() || self.family != other.family {
+            return false;
+        }
+        if self.given.len() != other.given.len() {
+            return false;
+        }
+        self.given.iter().zip(&other.given).all(|(a, b)| {
+            a == b
+                || (a.chars().count() == 1 && b.starts_with(a.as_str()))
+                || (b.chars().count() == 1 && a.starts_with(b.as_str()))
+        })
+    }
+}
+
+/// Split a name into lowercase, accent-free given names and family name.
+fn split_name(name: &str) -> (Vec<String>, String) {
+    let clean = |word: &str| {
+        text::strip_accents(word)
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '-')
+            .collect::<String>()
+            .trim_matches('-')
+            .to_string()
+    };
+    // `Jr.` is a suffix but `J.R.` initials.
+    let is_name = |raw: &str, w: &String| {
+        let bare = raw.trim_end_matches(['.', ',']).to_lowercase();
+        !w.is_empty() && !SUFFIXES.contains(&bare.as_str())
+    };
+    let given_names = |part: &str| text::tokenize(&text::strip_accents(part));
+
+    // `Smith, John`, `van der Berg, Maria` and `Berg, Maria van der`
+    if let Some((family, given)) = name.split_once(',') {
+        let mut given = given_names(given.split(',').next().unwrap_or_default());
+        let mut family: Vec<String> = family
+            .split_whitespace()
+            .filter_map(|w| Some(clean(w)).filter(|c| is_name(w, c)))
+            .collect();
+        while given.len() > 1 && PARTICLES.contains(&given[given.len() - 1].as_str()) {
+            family.insert(0, given.pop().unwrap_or_default());
+        }
+        return (given, family.join(" "));
+    }
+
+    let words: Vec<(&str, String)> = name
+        .split_whitespace()
+        .map(|w| (w, clean(w)))
+        .filter(|(raw, w)| is_name(raw, w))
+        .collect();
+    let mut start = words.len().saturating_sub(1);
+    while start > 1 && PARTICLES.contains(&words[start - 1].1.as_str()) {
+        start -= 1;
+    }
+    let given = words[..start]
+        .iter()
+        .flat_map(|(raw, _)| given_names(raw))
+        .collect();
+    let family: Vec<&str> = words[start..].iter().map(|(_, w)| w.as_str()).collect();
+    (given, family.join(" "))
+}
+
+/// Canonical author names and the other ways they are written, from `[author_aliases]`.
+pub struct AuthorAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl AuthorAliases {
+    pub fn new(config: &HashMap<String, Vec<String>>) -> AuthorAliases {
+        let mut aliases = HashMap::new();
+        for (canonical, variants) in config {
+            for variant in variants {
+                let variant = Author::new(variant, None);
+                aliases.insert(variant.normalized, canonical.clone());
+            }
+        }
+        AuthorAliases { aliases }
+    }
+
+    pub fn resolve(&self, author: &mut Author) {
+        if let Some(canonical) = self.aliases.get(&author.normalized) {
+            author.set_canonical(canonical);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(a: &str, b: &str) -> bool {
+        Author::new(a, None).matches(&Author::new(b, None))
+    }
+
+    #[test]
+    fn initials() {
+        assert!(same("J. Smith", "John Smith"));
+        assert!(same("J.R. Smith", "John Robert Smith"));
+        assert!(same("John R. Smith", "J. Robert Smith"));
+        assert!(!same("J. R. Smith", "John Smith"));
+        assert!(!same("John Smith", "John Robert Smith"));
+        assert!(!same("J. Smith", "Jane Doe"));
+        assert!(!same("R. J. Smith", "John Robert Smith"));
+        assert!(!same("Smith", "John Smith"));
+        assert_eq!(Author::new("J. R. Smith Jr.", None).key, "smith_jr");
+    }
+
+    #[test]
+    fn family_first() {
+        assert!(same("Smith, John", "John Smith"));
+        assert!(same("Smith, J.", "John Smith"));
+        assert!(!same("John, Smith", "John Smith"));
+        let author = Author::new("Smith, John, Jr.", None);
+        assert_eq!(author.normalized, "john smith");
+        assert_eq!(author.canonical, "Smith, John, Jr.");
+    }
+
+    #[test]
+    fn particles() {
+        let forms = [
+            "Ludwig van Beethoven",
+            "van Beethoven, Ludwig",
+            "Beethoven, Ludwig van",
+        ];
+        for form in forms {
+            let author = Author::new(form, None);
+            assert_eq!(author.normalized, "ludwig van beethoven", "{}", form);
+            assert_eq!(author.key, "van_beethoven_l", "{}", form);
+        }
+        assert!(same("Maria van der Berg", "Berg, Maria van der"));
+        assert!(same("M. van der Berg", "van der Berg, Maria"));
+        // A single name is never taken for a particle.
+        assert_eq!(Author::new("Le, Quoc", None).normalized, "quoc le");
+    }
+
+    #[test]
+    fn accents() {
+        assert!(same("Kurt Gödel", "Kurt Godel"));
+        assert!(same("Gödel, K.", "Kurt Godel"));
+        assert_eq!(Author::new("Ångström, Anders", None).key, "angstrom_a");
+        assert!(!same("Kurt Gödel", "Kurt Goedel"));
+    }
+
+    #[test]
+    fn aliases() {
+        let config = HashMap::from([(
+            "Jane Doe".to_string(),
+            vec!["J. Roe".to_string(), "Roe, Jane".to_string()],
+        )]);
+        let aliases = AuthorAliases::new(&config);
+        for name in ["Jane Roe", "J. Roe", "Roe, J."] {
+            let mut author = Author::new(name, None);
+            aliases.resolve(&mut author);
+            assert_eq!(author.canonical, "Jane Doe", "{}", name);
+            assert_eq!(author.key, "doe_j");
+            assert_eq!(author.name, name);
+        }
+        let mut other = Author::new("John Roe", None);
+        aliases.resolve(&mut other);
+        assert_eq!(other.canonical, "John Roe");
+        assert!(!same("", ""));
+    }
+
+    #[test]
+    fn search_query() {
+        assert_eq!(Author::new("John Smith", None).search_query(), "au:smith_j");
+        assert_eq!(
+            Author::new("Smith, J. R.", None).search_query(),
+            "au:smith_j"
+        );
+        assert_eq!(
+            Author::new("Maria van der Berg", None).search_query(),
+            "au:van_der_berg_m"
+        );
+        assert_eq!(
+            Author::new("José García-Pérez", None).search_query(),
+            "au:garcia_perez_j"
+        );
+        assert_eq!(Author::new("Plato", None).search_query(), "au:plato");
+    }
+}
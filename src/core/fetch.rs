@@ -1,7 +1,8 @@
+use super::author::Author;
 use super::structs::{Arxiv, ArxivQuery};
 use crate::{ArxivCollection, Config};
 use anyhow::Result;
-use reqwest::{Client, IntoUrl};
+use reqwest::{Client, IntoUrl, Url};
 use serde::de::DeserializeOwned;
 use std::fs;
 use std::fs::File;
@@ -20,7 +21,7 @@ pub async fn feed_cache<T, S>(url: T, client: &Client) -> Result<S>
 pub async fn from_cache(url: &Option<String>, client: &Client) -> ArxivCollection {
     if let Some(cache_url) = url {
         info!("Feeding rss cache from {}", cache_url);
-        match feed_cache(cache_url, &client).await {
+        match feed_cache(cache_url, client).await {
             Ok(rss) => {
                 info!("Feed rss cache Successfully!");
                 rss
@@ -35,6 +36,32 @@ pub async fn from_cache(url: &Option<String>, client: &Client) -> ArxivCollectio
     }
 }
 
+/// The liked papers of the last build, from `seeds.json` next to the cache.
+pub async fn seeds_from_cache(url: &Option<String>, client: &Client) -> Vec<Arxiv> {
+    let seeds_url = url
+        .as_deref()
+        .map(|url| Url::parse(url).and_then(|url| url.join("seeds.json")));
+    let seeds_url = match seeds_url {
+        Some(Ok(seeds_url)) => seeds_url,
+        _ => return Vec::new(),
+    };
+    info!("Feeding liked papers from {}", seeds_url);
+    feed_cache(seeds_url, client).await.unwrap_or_else(|err| {
+        warn!("Failed: {}!", err.to_string());
+        Vec::new()
+    })
+}
+
+pub fn dump_seeds(seeds: &[Arxiv], config: &Config) -> Result<()> {
+    fs::create_dir_all(&config.target_dir)?;
+    let seeds_path = Path::new(&config.target_dir).join("seeds.json");
+
+    info!("Dumping Liked Papers: {}", seeds_path.to_string_lossy());
+    let mut f = File::create(seeds_path)?;
+    serde_json::to_writer(&mut f, seeds)?;
+    Ok(())
+}
+
 pub fn dump_cache(cache_data: &ArxivCollection, config: &Config) -> Result<()> {
     fs::create_dir_all(&config.target_dir)?;
     let cache_path = Path::new(&config.target_dir).join("cache.json");
@@ -101,19 +128,31 @@ fn parse_data(body: String) -> Result<Vec<Arxiv>> {
                     }
                 }
                 "author" => {
-                    parser.next()?;
-                    parser.next()?;
-                    if let XmlEvent::Characters(author) = parser.next()? {
-                        arxiv.authors.push(author);
+                    let mut author = String::new();
+                    let mut affiliation = None;
+                    loop {
+                        match parser.next()? {
+                            XmlEvent::StartElement { name, .. } => {
+                                if let XmlEvent::Characters(text) = parser.next()? {
+                                    match &name.local_name[..] {
+                                        "name" => author = text,
+                                        "affiliation" => affiliation = Some(text),
+                                        _ => (),
+                                    }
+                                }
+                            }
+                            XmlEvent::EndElement { name } if name.local_name == "author" => break,
+                            XmlEvent::EndDocument => break,
+                            _ => (),
+                        }
                     }
+                    arxiv.authors.push(Author::new(&author, affiliation));
                 }
-                "link" => {
-                    if attributes[0].value == "pdf" {
-                        arxiv.pdf_url = format!(
-                            "{}.pdf",
-                            attributes[1].value.replacen("http", "https", 1).clone()
-                        );
-                    }
+                "link" if attributes[0].value == "pdf" => {
+                    arxiv.pdf_url = format!(
+                        "{}.pdf",
+                        attributes[1].value.replacen("http", "https", 1).clone()
+                    );
                 }
                 "comment" => {
                     if let XmlEvent::Characters(comment) = parser.next()? {
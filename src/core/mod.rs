@@ -1,7 +1,12 @@
+mod author;
 mod macros;
 mod structs;
 mod fetch;
 mod query;
 
-pub use structs::{Arxiv, ArxivDaily, ArxivQuery, ArxivCollection, ArxivQueryBuilder};
-pub use fetch::{dump_cache, fetch_arxivs, from_cache};
\ No newline at end of file
+pub use author::{Author, AuthorAliases};
+pub use structs::{
+    Arxiv, ArxivCategory, ArxivCollection, ArxivDaily, ArxivQueryBuilder, ArxivTopic, Embedding,
+    RelatedPaper, SEMANTIC_SECTION, versionless_id,
+};
+pub use fetch::{dump_cache, dump_seeds, fetch_arxivs, from_cache, seeds_from_cache};
\ No newline at end of file
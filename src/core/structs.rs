@@ -1,12 +1,19 @@
+use super::author::Author;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
 
 pub type ArxivCollection = IndexMap<DateTime<Utc>, IndexMap<String, IndexSet<Arxiv>>>;
 
+/// Prefix of the cached sections of `[[semantic_sources]]`, so that they never replace the
+/// section of a fetched source with the same title.
+pub const SEMANTIC_SECTION: &str = "semantic:";
+
 #[derive(Serialize, Deserialize, Debug, Hash, Clone, Eq, PartialEq)]
 pub struct ArxivDaily {
     pub datetime: DateTime<Utc>,
@@ -14,11 +21,26 @@ pub struct ArxivDaily {
 }
 
 impl ArxivDaily {
+    /// The sections of a cached day, without the embeddings, which only the cache keeps.
     pub fn new(datetime: DateTime<Utc>, raw: IndexMap<String, IndexSet<Arxiv>>) -> ArxivDaily {
         let mut subjects = Vec::new();
         for (subject, collection) in raw {
-            let papers: Vec<Arxiv> = collection.into_iter().collect();
-            subjects.push(ArxivCategory { subject, papers })
+            let papers: Vec<Arxiv> = collection
+                .into_iter()
+                .map(|mut paper| {
+                    paper.embedding = None;
+                    paper
+                })
+                .collect();
+            subjects.push(ArxivCategory {
+                subject: match subject.strip_prefix(SEMANTIC_SECTION) {
+                    Some(title) => title.to_string(),
+                    None => subject,
+                },
+                papers,
+                hidden: 0,
+                topics: Vec::new(),
+            })
         }
         ArxivDaily { datetime, subjects }
     }
@@ -32,25 +54,100 @@ impl ArxivDaily {
 pub struct ArxivCategory {
     pub subject: String,
     pub papers: Vec<Arxiv>,
+    /// Number of muted papers.
+    #[serde(default)]
+    pub hidden: usize,
+    /// The papers grouped by topic, empty unless clustering is enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<ArxivTopic>,
+}
+
+/// Papers of a category sharing a topic, labelled by its top terms.
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Eq, PartialEq)]
+pub struct ArxivTopic {
+    pub label: String,
+    pub terms: Vec<String>,
+    pub papers: Vec<Arxiv>,
 }
 
 impl ArxivCategory {
     pub fn sort(&mut self) {
         self.papers.sort_by_key(|p| p.updated != p.published)
     }
+
+    /// Highest score first, keeping the current order between equal scores.
+    pub fn sort_by_score(&mut self) {
+        self.papers
+            .sort_by(|a, b| b.score.unwrap_or(0.0).total_cmp(&a.score.unwrap_or(0.0)))
+    }
 }
 
 /// A structure that stores the paper information.
-#[derive(Serialize, Deserialize, Debug, Hash, Clone, Eq, PartialEq)]
+///
+/// Papers are identified by their versioned `id`, so build-time annotations do not affect equality.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Arxiv {
     pub id: String,
     pub updated: DateTime<Utc>,
     pub published: DateTime<Utc>,
     pub title: String,
     pub summary: String,
-    pub authors: Vec<String>,
+    pub authors: Vec<Author>,
     pub pdf_url: String,
     pub comment: Option<String>,
+    /// One-sentence summary from a language model, cached with the paper.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tldr: Option<String>,
+    /// The abstract in other languages, keyed by language code.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, String>,
+    /// Only kept in the cache, rendered papers have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Embedding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_terms: Vec<String>,
+    /// Names of the watched authors of this paper.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watched: Vec<String>,
+    /// The most similar papers of the collection, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedPaper>,
+    /// Cosine similarity to the profile of liked papers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<f64>,
+}
+
+/// The embedding of a paper's title and abstract, recomputed when the model changes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Embedding {
+    pub model: String,
+    pub vector: Vec<f32>,
+}
+
+/// A link from a paper to a similar one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RelatedPaper {
+    pub id: String,
+    pub title: String,
+    pub updated: DateTime<Utc>,
+    /// Cosine similarity of their TF-IDF vectors, in `[0, 1]`.
+    pub similarity: f64,
+}
+
+impl PartialEq for Arxiv {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Arxiv {}
+
+impl Hash for Arxiv {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
 }
 
 #[allow(dead_code)]
@@ -65,9 +162,27 @@ impl Arxiv {
             authors: Default::default(),
             pdf_url: Default::default(),
             comment: Default::default(),
+            tldr: Default::default(),
+            translations: Default::default(),
+            embedding: Default::default(),
+            score: Default::default(),
+            matched_terms: Default::default(),
+            watched: Default::default(),
+            related: Default::default(),
+            affinity: Default::default(),
         }
     }
 
+    /// The identifier with its version, e.g. `2401.00001v2`.
+    pub fn versioned_id(&self) -> &str {
+        versioned_id(&self.id)
+    }
+
+    /// The identifier without its version, e.g. `2401.00001`, the same for every version.
+    pub fn versionless_id(&self) -> &str {
+        versionless_id(&self.id)
+    }
+
     /// Save the paper as a pdf from the information stored by the structure.
     pub async fn fetch_pdf(&self, out_path: &str) -> Result<()> {
         let body = reqwest::get(&self.pdf_url).await?.bytes().await?;
@@ -82,6 +197,24 @@ impl Arxiv {
     }
 }
 
+/// The identifier of an abstract URL or identifier, with its version.
+pub fn versioned_id(id: &str) -> &str {
+    id.split_once("/abs/").map_or(id, |(_, id)| id)
+}
+
+/// The identifier of an abstract URL or identifier, without its version.
+pub fn versionless_id(id: &str) -> &str {
+    let id = versioned_id(id);
+    match id.rfind('v') {
+        Some(index)
+            if index + 1 < id.len() && id[index + 1..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            &id[..index]
+        }
+        _ => id,
+    }
+}
+
 /// A structure that stores the query information.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ArxivQuery {
@@ -0,0 +1,135 @@
+use super::llm::OpenAi;
+use super::{run_limited, unique_papers, update_papers};
+use crate::config::{Embeddings, SemanticSource};
+use crate::core::{Arxiv, ArxivCollection, Embedding, SEMANTIC_SECTION};
+
+use anyhow::Result;
+use reqwest::Client;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Embed the papers lacking an embedding of the configured model, then fill the section of
+/// each semantic source with the papers of its day close enough to its description. Sections
+/// of the previous build are always dropped first, so they are gone when the descriptions
+/// cannot be embedded. Section papers are copies without embeddings, only the fetched sections
+/// keep them.
+pub async fn semantic_sources(
+    config: &Embeddings,
+    sources: &[SemanticSource],
+    client: &Client,
+    raw_data: &mut ArxivCollection,
+) -> Result<()> {
+    clear_sections(raw_data);
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let api = OpenAi::new(
+        client,
+        &config.base_url,
+        config.api_key_env.as_deref(),
+        config.timeout_secs,
+    )?;
+    let descriptions: Vec<String> = sources.iter().map(|s| s.description.clone()).collect();
+    let topics = match api.embeddings(&config.model, &descriptions).await {
+        Ok(topics) => topics,
+        Err(err) => {
+            warn!(
+                "Semantic sources dropped, cannot embed descriptions: {}",
+                err
+            );
+            return Ok(());
+        }
+    };
+
+    let mut pending = Vec::new();
+    for (id, paper) in unique_papers(raw_data) {
+        if paper
+            .embedding
+            .as_ref()
+            .is_none_or(|e| e.model != config.model)
+        {
+            pending.push((
+                id.to_string(),
+                format!("{}\n\n{}", paper.title, paper.summary),
+            ));
+        }
+    }
+    let mut vectors: HashMap<String, Vec<f32>> = HashMap::new();
+    if !pending.is_empty() {
+        info!("Embeddings: {} papers to embed", pending.len());
+        let jobs = pending
+            .chunks(config.batch_size.max(1))
+            .map(|batch| {
+                let api = api.clone();
+                let model = config.model.clone();
+                let (ids, texts): (Vec<String>, Vec<String>) = batch.iter().cloned().unzip();
+                (ids, async move { api.embeddings(&model, &texts).await })
+            })
+            .collect();
+        for (ids, result) in run_limited(jobs, config.concurrency, || false).await? {
+            match result {
+                Ok(embeddings) => vectors.extend(ids.into_iter().zip(embeddings)),
+                Err(err) => warn!("Embeddings of {} papers: {}", ids.len(), err),
+            }
+        }
+        info!("Embeddings: {} papers embedded", vectors.len());
+        update_papers(raw_data, |paper| {
+            if let Some(vector) = vectors.get(&paper.id) {
+                paper.embedding = Some(Embedding {
+                    model: config.model.clone(),
+                    vector: vector.clone(),
+                });
+            }
+        });
+    }
+
+    for sections in raw_data.values_mut() {
+        let mut semantic = Vec::new();
+        for (source, topic) in sources.iter().zip(&topics) {
+            let mut matched: Vec<(f64, &Arxiv)> = Vec::new();
+            for paper in sections.values().flatten() {
+                let similarity = match &paper.embedding {
+                    Some(e) if e.model == config.model => cosine(&e.vector, topic),
+                    _ => continue,
+                };
+                if similarity >= source.threshold && !matched.iter().any(|(_, p)| p == &paper) {
+                    matched.push((similarity, paper));
+                }
+            }
+            matched.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let matched: Vec<Arxiv> = matched
+                .into_iter()
+                .map(|(_, p)| Arxiv {
+                    embedding: None,
+                    ..p.clone()
+                })
+                .collect();
+            if !matched.is_empty() {
+                let key = format!("{}{}", SEMANTIC_SECTION, source.title);
+                semantic.push((key, matched.into_iter().collect()));
+            }
+        }
+        sections.extend(semantic);
+    }
+    Ok(())
+}
+
+fn clear_sections(raw_data: &mut ArxivCollection) {
+    for sections in raw_data.values_mut() {
+        sections.retain(|title, _| !title.starts_with(SEMANTIC_SECTION));
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| *x as f64 * *x as f64).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms > 0.0 {
+        dot / norms
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client;
+use serde_json::{json, Value as Json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::utils::env_var;
+
+/// A client of an OpenAI-compatible API, such as a llama.cpp or vLLM server.
+#[derive(Clone)]
+pub struct OpenAi {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl OpenAi {
+    pub fn new(
+        client: &Client,
+        base_url: &str,
+        api_key_env: Option<&str>,
+        timeout_secs: u64,
+    ) -> Result<OpenAi> {
+        Ok(OpenAi {
+            client: client.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: env_var(api_key_env)?,
+            timeout: Duration::from_secs(timeout_secs),
+        })
+    }
+
+    async fn post(&self, path: &str, body: &Json) -> Result<Json> {
+        let url = format!("{}/{}", self.base_url, path);
+        let mut request = self.client.post(&url).timeout(self.timeout).json(body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            bail!("{} returned {}: {}", url, status, text);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// The reply of `model` to `prompt`, and the tokens spent.
+    pub async fn chat(
+        &self,
+        model: &str,
+        system: Option<&str>,
+        prompt: &str,
+        max_tokens: u32,
+    ) -> Result<(String, u64)> {
+        let mut messages = Vec::new();
+        if let Some(system) = system {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        messages.push(json!({ "role": "user", "content": prompt }));
+        let body = json!({
+            "model": model,
+            "messages": messages,
+            "max_tokens": max_tokens,
+            "temperature": 0,
+        });
+        let response = self.post("chat/completions", &body).await?;
+        let reply = response
+            .pointer("/choices/0/message/content")
+            .and_then(Json::as_str)
+            .ok_or_else(|| anyhow!("No reply in {}", response))?
+            .trim()
+            .to_string();
+        // Servers not reporting usage are charged about 4 characters per token.
+        let tokens = response
+            .pointer("/usage/total_tokens")
+            .and_then(Json::as_u64)
+            .unwrap_or(((prompt.len() + reply.len()) / 4) as u64);
+        Ok((reply, tokens))
+    }
+
+    /// The embedding of each input, in order.
+    pub async fn embeddings(&self, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = json!({ "model": model, "input": inputs });
+        let response = self.post("embeddings", &body).await?;
+        let mut data: Vec<(u64, Vec<f32>)> = response
+            .get("data")
+            .and_then(Json::as_array)
+            .ok_or_else(|| anyhow!("No embeddings in {}", response))?
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let index = item.get("index").and_then(Json::as_u64).unwrap_or(i as u64);
+                let embedding = serde_json::from_value(item["embedding"].clone())?;
+                Ok((index, embedding))
+            })
+            .collect::<Result<_>>()?;
+        if data.len() != inputs.len() {
+            bail!("{} embeddings for {} inputs", data.len(), inputs.len());
+        }
+        data.sort_by_key(|(index, _)| *index);
+        Ok(data.into_iter().map(|(_, embedding)| embedding).collect())
+    }
+}
+
+/// Tokens spent by concurrent requests, against an optional limit.
+pub struct TokenBudget {
+    limit: Option<u64>,
+    used: AtomicU64,
+}
+
+impl TokenBudget {
+    pub fn new(limit: Option<u64>) -> TokenBudget {
+        TokenBudget {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    pub fn spend(&self, tokens: u64) {
+        self.used.fetch_add(tokens, Ordering::Relaxed);
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used() >= limit)
+    }
+}
@@ -0,0 +1,74 @@
+//! Annotations from external services, added before the cache is dumped so that each paper is
+//! only ever sent once.
+
+mod embed;
+mod llm;
+mod tldr;
+mod translate;
+
+pub use embed::semantic_sources;
+pub use tldr::tldr;
+pub use translate::translate;
+
+use crate::core::{Arxiv, ArxivCollection};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Apply `f` to every paper of the collection, keeping the order of each section.
+fn update_papers(raw_data: &mut ArxivCollection, mut f: impl FnMut(&mut Arxiv)) {
+    for section in raw_data.values_mut().flat_map(|d| d.values_mut()) {
+        *section = section
+            .drain(..)
+            .map(|mut paper| {
+                f(&mut paper);
+                paper
+            })
+            .collect();
+    }
+}
+
+/// Every paper once, by versioned ID.
+fn unique_papers(raw_data: &ArxivCollection) -> IndexMap<&str, &Arxiv> {
+    let mut papers = IndexMap::new();
+    for paper in raw_data.values().flat_map(|d| d.values()).flatten() {
+        papers.entry(paper.id.as_str()).or_insert(paper);
+    }
+    papers
+}
+
+/// Run the jobs, at most `concurrency` at once, and none after `stop` returns true. Jobs are
+/// returned with their key, in completion order.
+async fn run_limited<K, T, F>(
+    jobs: Vec<(K, F)>,
+    concurrency: usize,
+    stop: impl Fn() -> bool,
+) -> Result<Vec<(K, Result<T>)>>
+where
+    K: Send + 'static,
+    T: Send + 'static,
+    F: Future<Output = Result<T>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (key, job) in jobs {
+        let permit = semaphore.clone().acquire_owned().await?;
+        if stop() {
+            break;
+        }
+        tasks.spawn(async move {
+            let result = job.await;
+            drop(permit);
+            (key, result)
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined?);
+    }
+    Ok(results)
+}
@@ -0,0 +1,88 @@
+use super::llm::{OpenAi, TokenBudget};
+use super::{run_limited, unique_papers, update_papers};
+use crate::config::Tldr;
+use crate::core::ArxivCollection;
+
+use anyhow::Result;
+use handlebars::{no_escape, Handlebars};
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Add a TL;DR to the papers without one. Failed requests are logged and retried next build.
+pub async fn tldr(config: &Tldr, client: &Client, raw_data: &mut ArxivCollection) -> Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let api = OpenAi::new(
+        client,
+        &config.base_url,
+        config.api_key_env.as_deref(),
+        config.timeout_secs,
+    )?;
+    let mut prompts = Handlebars::new();
+    prompts.register_escape_fn(no_escape);
+    prompts.register_template_string("prompt", &config.prompt)?;
+
+    let papers = unique_papers(raw_data);
+    let mut tldrs: HashMap<String, String> = HashMap::new();
+    let mut pending = Vec::new();
+    for (id, paper) in papers {
+        match &paper.tldr {
+            Some(tldr) => {
+                tldrs.insert(id.to_string(), tldr.clone());
+            }
+            None => pending.push((id.to_string(), prompts.render("prompt", paper)?)),
+        }
+    }
+    if pending.is_empty() {
+        return Ok(());
+    }
+    info!("TL;DR: {} papers to summarize", pending.len());
+
+    let total = pending.len();
+    let budget = Arc::new(TokenBudget::new(config.token_budget));
+    let jobs = pending
+        .into_iter()
+        .map(|(id, prompt)| {
+            let api = api.clone();
+            let budget = budget.clone();
+            let model = config.model.clone();
+            let system = config.system.clone();
+            let max_tokens = config.max_tokens;
+            let job = async move {
+                let (reply, tokens) = api
+                    .chat(&model, system.as_deref(), &prompt, max_tokens)
+                    .await?;
+                budget.spend(tokens);
+                Ok(reply)
+            };
+            (id, job)
+        })
+        .collect();
+    let results = run_limited(jobs, config.concurrency, || budget.exhausted()).await?;
+    if results.len() < total {
+        warn!("TL;DR: token budget spent, the remaining papers wait for the next build");
+    }
+
+    let mut written = 0;
+    for (id, result) in results {
+        match result {
+            Ok(reply) if !reply.is_empty() => {
+                tldrs.insert(id, reply);
+                written += 1;
+            }
+            Ok(_) => warn!("TL;DR of {}: empty reply", id),
+            Err(err) => warn!("TL;DR of {}: {}", id, err),
+        }
+    }
+    info!("TL;DR: {} written, {} tokens spent", written, budget.used());
+
+    update_papers(raw_data, |paper| {
+        if paper.tldr.is_none() {
+            paper.tldr = tldrs.get(&paper.id).cloned();
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use super::llm::OpenAi;
+use super::{run_limited, unique_papers, update_papers};
+use crate::config::{Translate, TranslateBackend};
+use crate::core::ArxivCollection;
+use crate::utils::env_var;
+
+use anyhow::{anyhow, bail, Result};
+use handlebars::{no_escape, Handlebars};
+use reqwest::Client;
+use serde_json::{json, Value as Json};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A machine translation service.
+pub trait Translator: Send + Sync {
+    /// The abstract `text` of the paper `title`, in the language `lang`.
+    fn translate<'a>(
+        &'a self,
+        title: &'a str,
+        text: &'a str,
+        lang: &'a str,
+    ) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Translation by a chat model, prompted with a template.
+pub struct OpenAiTranslator {
+    api: OpenAi,
+    model: String,
+    prompt: Handlebars<'static>,
+    max_tokens: u32,
+}
+
+impl OpenAiTranslator {
+    pub fn new(config: &Translate, client: &Client) -> Result<OpenAiTranslator> {
+        let mut prompt = Handlebars::new();
+        prompt.register_escape_fn(no_escape);
+        prompt.register_template_string("prompt", &config.prompt)?;
+        Ok(OpenAiTranslator {
+            api: OpenAi::new(
+                client,
+                &config.base_url,
+                config.api_key_env.as_deref(),
+                config.timeout_secs,
+            )?,
+            model: config.model.clone(),
+            prompt,
+            max_tokens: config.max_tokens,
+        })
+    }
+}
+
+impl Translator for OpenAiTranslator {
+    fn translate<'a>(
+        &'a self,
+        title: &'a str,
+        text: &'a str,
+        lang: &'a str,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let data = json!({ "lang": lang, "title": title, "text": text });
+            let prompt = self.prompt.render("prompt", &data)?;
+            let (reply, _) = self
+                .api
+                .chat(&self.model, None, &prompt, self.max_tokens)
+                .await?;
+            Ok(reply)
+        })
+    }
+}
+
+/// Translation by a LibreTranslate server.
+pub struct LibreTranslator {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl LibreTranslator {
+    pub fn new(config: &Translate, client: &Client) -> Result<LibreTranslator> {
+        Ok(LibreTranslator {
+            client: client.clone(),
+            url: format!("{}/translate", config.base_url.trim_end_matches('/')),
+            api_key: env_var(config.api_key_env.as_deref())?,
+            timeout: Duration::from_secs(config.timeout_secs),
+        })
+    }
+}
+
+impl Translator for LibreTranslator {
+    fn translate<'a>(
+        &'a self,
+        _title: &'a str,
+        text: &'a str,
+        lang: &'a str,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let mut body = json!({ "q": text, "source": "auto", "target": lang, "format": "text" });
+            if let Some(api_key) = &self.api_key {
+                body["api_key"] = json!(api_key);
+            }
+            let response = self
+                .client
+                .post(&self.url)
+                .timeout(self.timeout)
+                .json(&body)
+                .send()
+                .await?;
+            let status = response.status();
+            let text = response.text().await?;
+            if !status.is_success() {
+                bail!("{} returned {}: {}", self.url, status, text);
+            }
+            let response: Json = serde_json::from_str(&text)?;
+            Ok(response
+                .get("translatedText")
+                .and_then(Json::as_str)
+                .ok_or_else(|| anyhow!("No translation in {}", response))?
+                .trim()
+                .to_string())
+        })
+    }
+}
+
+/// Translate the abstracts into each configured language they lack. Failures are logged and
+/// retried next build, templates fall back to the original abstract meanwhile. After
+/// `max_failures` in a row, the remaining abstracts are left for the next build too.
+pub async fn translate(
+    config: &Translate,
+    client: &Client,
+    raw_data: &mut ArxivCollection,
+) -> Result<()> {
+    if config.languages.is_empty() {
+        return Ok(());
+    }
+    let translator: Arc<dyn Translator> = match config.backend {
+        TranslateBackend::OpenAi => Arc::new(OpenAiTranslator::new(config, client)?),
+        TranslateBackend::LibreTranslate => Arc::new(LibreTranslator::new(config, client)?),
+    };
+
+    let failures = Arc::new(AtomicUsize::new(0));
+    let papers = unique_papers(raw_data);
+    let mut translations: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+    let mut jobs = Vec::new();
+    for (id, paper) in papers {
+        translations.insert(id.to_string(), paper.translations.clone());
+        for lang in config.languages.iter() {
+            if paper.translations.contains_key(lang) {
+                continue;
+            }
+            let (translator, failures) = (translator.clone(), failures.clone());
+            let (title, text, target) = (paper.title.clone(), paper.summary.clone(), lang.clone());
+            let job = async move {
+                let result = translator.translate(&title, &text, &target).await;
+                match &result {
+                    Ok(_) => failures.store(0, Ordering::Relaxed),
+                    Err(_) => _ = failures.fetch_add(1, Ordering::Relaxed),
+                }
+                result
+            };
+            jobs.push(((id.to_string(), lang.clone()), job));
+        }
+    }
+    if jobs.is_empty() {
+        return Ok(());
+    }
+    info!("Translate: {} abstracts to translate", jobs.len());
+
+    let total = jobs.len();
+    let stop =
+        || config.max_failures > 0 && failures.load(Ordering::Relaxed) >= config.max_failures;
+    let results = run_limited(jobs, config.concurrency, stop).await?;
+    if results.len() < total {
+        warn!(
+            "Translate: {} failures in a row, the remaining abstracts wait for the next build",
+            config.max_failures
+        );
+    }
+    let mut written = 0;
+    for ((id, lang), result) in results {
+        match result {
+            Ok(text) if !text.is_empty() => {
+                translations.entry(id).or_default().insert(lang, text);
+                written += 1;
+            }
+            Ok(_) => warn!("Translate {} into {}: empty translation", id, lang),
+            Err(err) => warn!("Translate {} into {}: {}", id, lang, err),
+        }
+    }
+    info!("Translate: {} abstracts translated", written);
+
+    update_papers(raw_data, |paper| {
+        if let Some(done) = translations.get(&paper.id) {
+            for (lang, text) in done {
+                paper
+                    .translations
+                    .entry(lang.clone())
+                    .or_insert_with(|| text.clone());
+            }
+        }
+    });
+    Ok(())
+}
@@ -1,15 +1,25 @@
+mod cli;
 mod config;
 mod utils;
 mod core;
+mod enrich;
+mod notify;
+mod output;
+mod stages;
+mod text;
 mod v1;
+mod v2;
 
 use anyhow::Result;
-use chrono::{Duration, Utc};
-use tracing::{info, span};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tracing::{info, span, warn, Span};
 
-use crate::config::{Config, Version};
-use crate::core::{dump_cache, fetch_arxivs, from_cache};
-use crate::core::{ArxivCollection, ArxivQueryBuilder};
+use crate::cli::Command;
+use crate::config::Config;
+use crate::core::{dump_cache, dump_seeds, fetch_arxivs, from_cache, seeds_from_cache};
+use crate::core::{Arxiv, ArxivCollection, ArxivQueryBuilder, Author};
+use crate::utils::read_id_file;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -20,7 +30,11 @@ async fn main() -> Result<()> {
     let root = span!(tracing::Level::INFO, "<FEED>");
     let _enter = root.enter();
 
-    let config = Config::new()?;
+    let config = Arc::new(Config::new()?);
+    if let Command::ScriptTest(args) = Command::from_args()? {
+        return v1::script_test(&config, &args);
+    }
+
     let client = reqwest::Client::builder().build()?;
 
     let today = Utc::now();
@@ -37,31 +51,110 @@ async fn main() -> Result<()> {
             .sort_order("descending")
             .build();
         let arxivs = fetch_arxivs(query, &client).await?;
-        for arxiv in arxivs {
-            let date = arxiv.updated;
-            if date >= cache_day {
-                let entry = raw_data.entry(date).or_default();
-                let entry = entry.entry(String::from(&source.title)).or_default();
-                entry.insert(arxiv);
-            }
-        }
+        collect(&mut raw_data, &source.title, arxivs, cache_day);
     }
+    for author in config.authors.iter().filter(|a| a.fetch > 0) {
+        info!("Get: {}", author.name);
+        let query = ArxivQueryBuilder::new()
+            .search_query(&Author::new(&author.name, None).search_query())
+            .start(0)
+            .max_results(author.fetch)
+            .sort_by("lastUpdatedDate")
+            .sort_order("descending")
+            .build();
+        let arxivs = fetch_arxivs(query, &client).await?;
+        collect(&mut raw_data, &config.watched_title, arxivs, cache_day);
+    }
+
+    let seeds = match &config.recommend.seeds_file {
+        Some(seeds_file) => {
+            let cached = seeds_from_cache(&config.cache_url, &client).await;
+            fetch_seeds(&read_id_file(seeds_file)?, &raw_data, cached, &client).await
+        }
+        None => Vec::new(),
+    };
 
-    let raw_data = raw_data
+    let mut raw_data = raw_data
         .into_iter()
         .filter(|(d, _)| d >= &cache_day)
         .collect();
+    enrich::tldr(&config.tldr, &client, &mut raw_data).await?;
+    enrich::translate(&config.translate, &client, &mut raw_data).await?;
+    enrich::semantic_sources(
+        &config.embeddings,
+        &config.semantic_sources,
+        &client,
+        &mut raw_data,
+    )
+    .await?;
 
     dump_cache(&raw_data, &config)?;
+    if config.recommend.seeds_file.is_some() {
+        dump_seeds(&seeds, &config)?;
+    }
 
-    match config.version {
-        Version::V1 => {
-            v1::main(&config, raw_data)?;
-        }
-        Version::V2 => {
-            todo!()
+    // Rendering blocks, on templates, files and the SMTP server of email outputs.
+    let render_config = config.clone();
+    let span = Span::current();
+    let data = tokio::task::spawn_blocking(move || {
+        let _enter = span.enter();
+        output::render(&render_config, raw_data, &seeds)
+    })
+    .await??;
+    notify::notify(&config, &client, &data).await;
+
+    Ok(())
+}
+
+/// Add the papers updated since `cache_day` to the `title` section of their day.
+fn collect(
+    raw_data: &mut ArxivCollection,
+    title: &str,
+    arxivs: Vec<Arxiv>,
+    cache_day: DateTime<Utc>,
+) {
+    for arxiv in arxivs {
+        let date = arxiv.updated;
+        if date >= cache_day {
+            let entry = raw_data.entry(date).or_default();
+            let entry = entry.entry(String::from(title)).or_default();
+            entry.insert(arxiv);
         }
     }
+}
 
-    Ok(())
+/// The liked papers, from the cache or the `cached` liked papers of the last build when there,
+/// otherwise from arXiv by `id_list`.
+async fn fetch_seeds(
+    ids: &[String],
+    raw_data: &ArxivCollection,
+    cached: Vec<Arxiv>,
+    client: &reqwest::Client,
+) -> Vec<Arxiv> {
+    let mut seeds = Vec::new();
+    let mut missing = Vec::new();
+    for id in ids {
+        let cached = raw_data
+            .values()
+            .flat_map(|s| s.values())
+            .flatten()
+            .chain(&cached)
+            .find(|p| p.versionless_id() == id || p.versioned_id() == id);
+        match cached {
+            Some(paper) => seeds.push(paper.clone()),
+            None => missing.push(id.as_str()),
+        }
+    }
+    for chunk in missing.chunks(100) {
+        info!("Get: {} liked papers", chunk.len());
+        let query = ArxivQueryBuilder::new()
+            .id_list(&chunk.join(","))
+            .max_results(chunk.len() as i32)
+            .build();
+        match fetch_arxivs(query, client).await {
+            Ok(arxivs) => seeds.extend(arxivs),
+            Err(err) => warn!("Cannot fetch liked papers: {}", err),
+        }
+    }
+    seeds
 }
@@ -0,0 +1,332 @@
+use anyhow::{anyhow, Result};
+use chrono::{SecondsFormat, Utc};
+use handlebars::Handlebars;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::info;
+
+use super::{load_ledger, new_days, save_ledger, send, truncate, NewPaper};
+use crate::config::{Bot, BotService, Config};
+use crate::crate_name;
+use crate::output::{guid, one_line};
+use crate::utils::env_var;
+use crate::v1::ArxivRender;
+
+const PAPER: &str = "{{title}}
+{{#each authors}}{{#unless @first}}, {{/unless}}{{canonical}}{{/each}}";
+
+/// Characters Mastodon counts for a link, whatever its length.
+const MASTODON_LINK_LENGTH: usize = 23;
+
+/// The papers posted by a bot, and the thread of each day.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Ledger {
+    /// Versionless IDs of the papers posted, with their status.
+    posted: BTreeMap<String, Status>,
+    /// The first and last statuses of the days still posted to.
+    threads: BTreeMap<String, Thread>,
+}
+
+/// A status, by its Mastodon ID, or by its AT URI and CID.
+#[derive(Clone, Deserialize, Serialize)]
+struct Status {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cid: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Thread {
+    root: Status,
+    last: Status,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    site_title: &'a str,
+    base_url: Option<&'a str>,
+    date: &'a str,
+    papers: usize,
+}
+
+/// The credentials of a logged in account.
+enum Account {
+    Mastodon { token: String },
+    Atproto { did: String, jwt: String },
+}
+
+/// Post the papers passing the filter of `bot` that its ledger does not list yet, oldest day
+/// first. The ledger is saved after each status, so that a failed build never posts twice.
+pub async fn post(
+    config: &Config,
+    bot: &Bot,
+    index: usize,
+    client: &Client,
+    data: &ArxivRender,
+) -> Result<()> {
+    let path = match &bot.ledger {
+        Some(path) => path.clone(),
+        None => config.ledger_path(
+            &format!("posted-{}", service_name(bot.service)),
+            &[
+                service_name(bot.service),
+                &bot.url,
+                bot.identifier.as_deref().unwrap_or_default(),
+                &bot.token_env,
+            ],
+            "json",
+        ),
+    };
+    let mut ledger: Ledger = load_ledger(&path)?;
+    let days = new_days(data, bot.days, &bot.filter);
+    ledger
+        .threads
+        .retain(|date, _| days.iter().any(|(d, _)| d == date));
+    let pending: Vec<(&str, Vec<&NewPaper>)> = days
+        .iter()
+        .rev()
+        .filter_map(|(date, papers)| {
+            let papers: Vec<&NewPaper> = papers
+                .iter()
+                .filter(|p| !ledger.posted.contains_key(p.paper.versionless_id()))
+                .collect();
+            (!papers.is_empty()).then_some((date.as_str(), papers))
+        })
+        .collect();
+    if pending.is_empty() {
+        info!("No new papers for {:?} bot #{}", bot.service, index);
+        return save_ledger(&path, &ledger);
+    }
+
+    let mut hbs = Handlebars::new();
+    hbs.register_escape_fn(one_line);
+    hbs.register_template_string("paper", bot.template.as_deref().unwrap_or(PAPER))?;
+    if let Some(header) = &bot.header {
+        hbs.register_template_string("header", header)?;
+    }
+    let account = login(bot, client).await?;
+    let max_length = bot.max_length.unwrap_or(match bot.service {
+        BotService::Mastodon => 500,
+        BotService::Atproto => 300,
+    });
+    let interval = Duration::from_secs(bot.interval_secs);
+
+    let mut posted = 0;
+    'days: for (date, papers) in pending {
+        let mut thread = ledger.threads.get(date).cloned();
+        if bot.thread && thread.is_none() && bot.header.is_some() {
+            if posted == bot.max_posts {
+                break;
+            }
+            let header = hbs.render(
+                "header",
+                &Header {
+                    site_title: &config.site_title,
+                    base_url: config.base_url.as_deref(),
+                    date,
+                    papers: papers.len(),
+                },
+            )?;
+            let key = format!("{}-{}", crate_name!(), date);
+            let status = publish(
+                bot,
+                client,
+                &account,
+                &key,
+                &truncate(&header, max_length),
+                None,
+                None,
+            )
+            .await?;
+            posted += 1;
+            let opened = Thread {
+                root: status.clone(),
+                last: status,
+            };
+            ledger.threads.insert(date.to_string(), opened.clone());
+            save_ledger(&path, &ledger)?;
+            thread = Some(opened);
+        }
+
+        for paper in papers {
+            if posted == bot.max_posts {
+                break 'days;
+            }
+            if posted > 0 {
+                tokio::time::sleep(interval).await;
+            }
+            let id = paper.paper.versionless_id();
+            let link = guid(paper.paper);
+            let link_length = match bot.service {
+                BotService::Mastodon => MASTODON_LINK_LENGTH,
+                BotService::Atproto => link.chars().count(),
+            };
+            let text = hbs.render("paper", paper)?;
+            let text = format!(
+                "{}\n\n{}",
+                truncate(&text, max_length.saturating_sub(link_length + 2)),
+                link
+            );
+            let key = format!("{}-{}", crate_name!(), id);
+            let reply = thread.as_ref().filter(|_| bot.thread);
+            let status = publish(
+                bot,
+                client,
+                &account,
+                &key,
+                &text,
+                Some((paper, &link)),
+                reply,
+            )
+            .await?;
+            posted += 1;
+            ledger.posted.insert(id.to_string(), status.clone());
+            if bot.thread {
+                let root = thread.map_or_else(|| status.clone(), |t| t.root);
+                let next = Thread { root, last: status };
+                ledger.threads.insert(date.to_string(), next.clone());
+                thread = Some(next);
+            }
+            save_ledger(&path, &ledger)?;
+        }
+    }
+    info!(
+        "{} statuses posted by {:?} bot #{}",
+        posted, bot.service, index
+    );
+    Ok(())
+}
+
+fn service_name(service: BotService) -> &'static str {
+    match service {
+        BotService::Mastodon => "mastodon",
+        BotService::Atproto => "atproto",
+    }
+}
+
+async fn login(bot: &Bot, client: &Client) -> Result<Account> {
+    let secret = env_var(Some(&bot.token_env))?.unwrap_or_default();
+    match bot.service {
+        BotService::Mastodon => Ok(Account::Mastodon { token: secret }),
+        BotService::Atproto => {
+            let identifier = bot
+                .identifier
+                .as_deref()
+                .ok_or_else(|| anyhow!("No AT Protocol identifier"))?;
+            let url = format!(
+                "{}/xrpc/com.atproto.server.createSession",
+                bot.url.trim_end_matches('/')
+            );
+            let request = client
+                .post(&url)
+                .timeout(Duration::from_secs(bot.timeout_secs))
+                .json(&json!({ "identifier": identifier, "password": secret }));
+            let session: Json = send(request).await?.json().await?;
+            let field = |name: &str| {
+                session[name]
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| anyhow!("No {} in the session of {}", name, identifier))
+            };
+            Ok(Account::Atproto {
+                did: field("did")?,
+                jwt: field("accessJwt")?,
+            })
+        }
+    }
+}
+
+/// Post `text` as `account`, in reply to the last status of `thread`. A paper's status ends with
+/// its `link`, which Bluesky also shows as a card.
+async fn publish(
+    bot: &Bot,
+    client: &Client,
+    account: &Account,
+    key: &str,
+    text: &str,
+    paper: Option<(&NewPaper<'_>, &str)>,
+    thread: Option<&Thread>,
+) -> Result<Status> {
+    let url = bot.url.trim_end_matches('/');
+    let timeout = Duration::from_secs(bot.timeout_secs);
+    match account {
+        Account::Mastodon { token } => {
+            let mut body = json!({ "status": text, "visibility": bot.visibility });
+            if let Some(language) = &bot.language {
+                body["language"] = json!(language);
+            }
+            if let Some(thread) = thread {
+                body["in_reply_to_id"] = json!(thread.last.id);
+            }
+            let request = client
+                .post(format!("{}/api/v1/statuses", url))
+                .timeout(timeout)
+                .bearer_auth(token)
+                .header("Idempotency-Key", key)
+                .json(&body);
+            let status: Json = send(request).await?.json().await?;
+            Ok(Status {
+                id: status["id"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("No id in {}", status))?
+                    .to_string(),
+                cid: None,
+            })
+        }
+        Account::Atproto { did, jwt } => {
+            let mut record = json!({
+                "$type": "app.bsky.feed.post",
+                "text": text,
+                "createdAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            });
+            if let Some(language) = &bot.language {
+                record["langs"] = json!([language]);
+            }
+            if let Some((paper, link)) = paper {
+                record["facets"] = json!([{
+                    "index": { "byteStart": text.len() - link.len(), "byteEnd": text.len() },
+                    "features": [{ "$type": "app.bsky.richtext.facet#link", "uri": link }],
+                }]);
+                record["embed"] = json!({
+                    "$type": "app.bsky.embed.external",
+                    "external": {
+                        "uri": link,
+                        "title": one_line(&paper.paper.title),
+                        "description": truncate(&one_line(&paper.paper.summary), 300),
+                    },
+                });
+            }
+            if let Some(thread) = thread {
+                let reference = |s: &Status| json!({ "uri": s.id, "cid": s.cid });
+                record["reply"] = json!({
+                    "root": reference(&thread.root),
+                    "parent": reference(&thread.last),
+                });
+            }
+            let request = client
+                .post(format!("{}/xrpc/com.atproto.repo.createRecord", url))
+                .timeout(timeout)
+                .bearer_auth(jwt)
+                .json(&json!({
+                    "repo": did,
+                    "collection": "app.bsky.feed.post",
+                    "record": record,
+                }));
+            let created: Json = send(request).await?.json().await?;
+            let field = |name: &str| {
+                created[name]
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| anyhow!("No {} in {}", name, created))
+            };
+            Ok(Status {
+                id: field("uri")?,
+                cid: Some(field("cid")?),
+            })
+        }
+    }
+}
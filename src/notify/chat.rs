@@ -0,0 +1,368 @@
+use anyhow::{anyhow, bail, Result};
+use handlebars::Handlebars;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::time::Duration;
+use tracing::info;
+
+use super::{load_ledger, new_papers, save_ledger, send, truncate, NewPaper};
+use crate::config::{ChatService, Config, Notifier};
+use crate::crate_name;
+use crate::output::one_line;
+use crate::utils::env_var;
+use crate::v1::ArxivRender;
+
+const SLACK_HEADER: &str = "*{{site_title}}*: {{papers}} new papers, {{dates}}";
+const SLACK_PAPER: &str = "• <{{id}}|{{title}}>{{#if score}} `{{score}}`{{/if}}
+    _{{#each authors}}{{#unless @first}}, {{/unless}}{{canonical}}{{/each}}_";
+
+const DISCORD_HEADER: &str = "**{{site_title}}**: {{papers}} new papers, {{dates}}";
+const DISCORD_PAPER: &str = "- [{{title}}](<{{id}}>){{#if score}} `{{score}}`{{/if}}
+  *{{#each authors}}{{#unless @first}}, {{/unless}}{{canonical}}{{/each}}*";
+
+const TEXT_HEADER: &str = "{{site_title}}: {{papers}} new papers, {{dates}}";
+const TEXT_PAPER: &str = "• {{title}}{{#if score}} [{{score}}]{{/if}}
+  {{#each authors}}{{#unless @first}}, {{/unless}}{{canonical}}{{/each}}
+  {{id}}";
+
+/// The header and paper templates, and the message length limit of `service`.
+fn defaults(service: ChatService) -> (&'static str, &'static str, usize) {
+    match service {
+        ChatService::Slack => (SLACK_HEADER, SLACK_PAPER, 4000),
+        ChatService::Discord => (DISCORD_HEADER, DISCORD_PAPER, 2000),
+        ChatService::Matrix => (TEXT_HEADER, TEXT_PAPER, 16000),
+        ChatService::Generic => (TEXT_HEADER, TEXT_PAPER, usize::MAX),
+    }
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    site_title: &'a str,
+    base_url: Option<&'a str>,
+    dates: &'a str,
+    papers: usize,
+}
+
+/// The papers announced by a notifier.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Ledger {
+    /// Versionless IDs of the papers announced.
+    announced: BTreeSet<String>,
+}
+
+/// A message, with the range of the papers it lists.
+struct Chunk {
+    text: String,
+    papers: Range<usize>,
+}
+
+/// Post the new papers passing the filter of `notifier` that its ledger does not list yet, in as
+/// many messages as it takes. The ledger is saved after each message.
+pub async fn post(
+    config: &Config,
+    notifier: &Notifier,
+    index: usize,
+    client: &Client,
+    data: &ArxivRender,
+) -> Result<()> {
+    let service = service_name(notifier.service);
+    let path = match &notifier.ledger {
+        Some(path) => path.clone(),
+        // The variable rather than the URL it holds, which is a secret.
+        None => config.ledger_path(
+            &format!("notified-{}", service),
+            &[
+                service,
+                notifier
+                    .url_env
+                    .as_deref()
+                    .or(notifier.url.as_deref())
+                    .unwrap_or_default(),
+                notifier.room.as_deref().unwrap_or_default(),
+            ],
+            "json",
+        ),
+    };
+    let mut ledger: Ledger = load_ledger(&path)?;
+    let (dates, papers) = new_papers(data, notifier.days, &notifier.filter, &ledger.announced);
+    if papers.is_empty() {
+        info!("No new papers for {:?} #{}", notifier.service, index);
+        return Ok(());
+    }
+
+    let (header, template, max_length) = defaults(notifier.service);
+    let mut hbs = Handlebars::new();
+    hbs.register_escape_fn(match notifier.service {
+        ChatService::Slack => escape_slack,
+        ChatService::Discord => escape_discord,
+        ChatService::Matrix | ChatService::Generic => one_line,
+    });
+    hbs.register_template_string("header", notifier.header.as_deref().unwrap_or(header))?;
+    hbs.register_template_string("paper", notifier.template.as_deref().unwrap_or(template))?;
+    let header = hbs.render(
+        "header",
+        &Header {
+            site_title: &config.site_title,
+            base_url: config.base_url.as_deref(),
+            dates: &dates,
+            papers: papers.len(),
+        },
+    )?;
+    let lines = papers
+        .iter()
+        .map(|paper| hbs.render("paper", paper))
+        .collect::<Result<Vec<_>, _>>()?;
+    let chunks = chunks(&header, &lines, notifier.max_length.unwrap_or(max_length));
+
+    let url = match (&notifier.url, &notifier.url_env) {
+        (_, Some(var)) => env_var(Some(var))?.unwrap_or_default(),
+        (Some(url), None) => url.clone(),
+        (None, None) => bail!("No `url` or `url_env`"),
+    };
+    let token = env_var(notifier.token_env.as_deref())?;
+    let timeout = Duration::from_secs(notifier.timeout_secs);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let request = match notifier.service {
+            ChatService::Slack => client.post(&url).json(&json!({ "text": chunk.text })),
+            ChatService::Discord => client.post(&url).json(&json!({
+                "content": chunk.text,
+                "allowed_mentions": { "parse": [] },
+            })),
+            ChatService::Matrix => {
+                let room = notifier
+                    .room
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("No Matrix room"))?;
+                // The same papers get the same transaction, which the homeserver sends once.
+                let txn = format!(
+                    "{}-{}-{}-{}",
+                    crate_name!(),
+                    index,
+                    papers[0].paper.versionless_id(),
+                    i
+                );
+                let token = token
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("No Matrix access token"))?;
+                client
+                    .put(matrix_url(&url, room, &txn)?)
+                    .bearer_auth(token)
+                    .json(&json!({ "msgtype": "m.notice", "body": chunk.text }))
+            }
+            ChatService::Generic => {
+                let papers: Vec<GenericPaper> = papers[chunk.papers.clone()]
+                    .iter()
+                    .map(GenericPaper::new)
+                    .collect();
+                let request = client.post(&url).json(&json!({
+                    "site_title": config.site_title,
+                    "dates": dates,
+                    "text": chunk.text,
+                    "papers": papers,
+                }));
+                match &token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+        };
+        send(request.timeout(timeout)).await?;
+        ledger.announced.extend(
+            papers[chunk.papers.clone()]
+                .iter()
+                .map(|p| p.paper.versionless_id().to_string()),
+        );
+        save_ledger(&path, &ledger)?;
+    }
+    info!(
+        "{} new papers posted to {:?} #{} in {} messages",
+        papers.len(),
+        notifier.service,
+        index,
+        chunks.len()
+    );
+    Ok(())
+}
+
+fn service_name(service: ChatService) -> &'static str {
+    match service {
+        ChatService::Slack => "slack",
+        ChatService::Discord => "discord",
+        ChatService::Matrix => "matrix",
+        ChatService::Generic => "generic",
+    }
+}
+
+/// The endpoint sending an event to `room`, percent-encoding the room ID.
+fn matrix_url(homeserver: &str, room: &str, txn: &str) -> Result<Url> {
+    let mut url = Url::parse(homeserver)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid homeserver URL {}", homeserver))?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            room,
+            "send",
+            "m.room.message",
+            txn,
+        ]);
+    Ok(url)
+}
+
+/// Messages of at most `limit` characters, the header then whole papers. A paper longer than a
+/// message is cut.
+fn chunks(header: &str, lines: &[String], limit: usize) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut text = truncate(header, limit);
+    let mut start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let line = truncate(line, limit);
+        if !text.is_empty() && text.chars().count() + 1 + line.chars().count() > limit {
+            chunks.push(Chunk {
+                text,
+                papers: start..i,
+            });
+            text = String::new();
+            start = i;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&line);
+    }
+    chunks.push(Chunk {
+        text,
+        papers: start..lines.len(),
+    });
+    chunks
+}
+
+/// Slack's `mrkdwn` only needs `&`, `<` and `>` escaped.
+fn escape_slack(text: &str) -> String {
+    one_line(text)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_discord(text: &str) -> String {
+    let text = one_line(text);
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '~' | '`' | '|' | '[' | ']' | '<' | '>' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A paper of a `generic` message.
+#[derive(Serialize)]
+struct GenericPaper<'a> {
+    id: &'a str,
+    url: &'a str,
+    pdf_url: &'a str,
+    title: String,
+    authors: Vec<&'a str>,
+    tldr: Option<&'a str>,
+    score: Option<f64>,
+    watched: &'a [String],
+    sources: &'a [&'a str],
+}
+
+impl<'a> GenericPaper<'a> {
+    fn new(new: &'a NewPaper) -> GenericPaper<'a> {
+        let paper = new.paper;
+        GenericPaper {
+            id: paper.versionless_id(),
+            url: &paper.id,
+            pdf_url: &paper.pdf_url,
+            title: one_line(&paper.title),
+            authors: paper.authors.iter().map(|a| a.canonical.as_str()).collect(),
+            tldr: paper.tldr.as_deref(),
+            score: paper.score,
+            watched: &paper.watched,
+            sources: &new.sources,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(messages: &[Chunk]) -> Vec<&str> {
+        messages.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn slack_escaping() {
+        assert_eq!(escape_slack(""), "");
+        assert_eq!(
+            escape_slack("Fast <b>R&D</b>\n  for *all*"),
+            "Fast &lt;b&gt;R&amp;D&lt;/b&gt; for *all*"
+        );
+        assert_eq!(escape_slack("&amp;"), "&amp;amp;");
+    }
+
+    #[test]
+    fn discord_escaping() {
+        assert_eq!(escape_discord(""), "");
+        assert_eq!(
+            escape_discord("A *bold* _claim_ ~~struck~~ `code`"),
+            r"A \*bold\* \_claim\_ \~\~struck\~\~ \`code\`"
+        );
+        assert_eq!(
+            escape_discord("[x](y) <z> ||spoiler|| # not a heading \\"),
+            r"\[x\](y) \<z\> \|\|spoiler\|\| \# not a heading \\"
+        );
+        assert_eq!(escape_discord("Two\nlines"), "Two lines");
+    }
+
+    #[test]
+    fn a_single_message() {
+        let lines = vec!["one".to_string()];
+        let messages = chunks("header", &lines, 100);
+        assert_eq!(texts(&messages), vec!["header\none"]);
+        assert_eq!(messages[0].papers, 0..1);
+
+        let messages = chunks("", &lines, 100);
+        assert_eq!(texts(&messages), vec!["one"]);
+    }
+
+    #[test]
+    fn messages_over_the_limit() {
+        let lines: Vec<String> = ["aaaa", "bbbb", "cccc"].map(String::from).to_vec();
+        // Exactly the limit still fits.
+        let messages = chunks("hh", &lines, 12);
+        assert_eq!(texts(&messages), vec!["hh\naaaa\nbbbb", "cccc"]);
+        assert_eq!(messages[0].papers, 0..2);
+        assert_eq!(messages[1].papers, 2..3);
+
+        // A header alone when the first paper does not fit after it.
+        let messages = chunks("header", &lines, 8);
+        assert_eq!(texts(&messages), vec!["header", "aaaa", "bbbb", "cccc"]);
+        assert_eq!(messages[0].papers, 0..0);
+        assert_eq!(messages[3].papers, 2..3);
+    }
+
+    #[test]
+    fn long_papers_are_cut() {
+        let lines = vec!["é".repeat(10), "short".to_string()];
+        let messages = chunks("", &lines, 6);
+        assert_eq!(texts(&messages), vec!["ééééé…", "short"]);
+        assert!(messages.iter().all(|c| c.text.chars().count() <= 6));
+        assert_eq!(truncate("header", 1), "…");
+    }
+}
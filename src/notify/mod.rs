@@ -0,0 +1,190 @@
+//! Posts of the new papers of a build, once every output is written. A failed post is logged and
+//! does not fail the build.
+
+mod bot;
+mod chat;
+
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexMap;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{Config, PaperFilter};
+use crate::core::{versionless_id, Arxiv};
+use crate::v1::ArxivRender;
+use crate::v2::Sitemap;
+
+/// Attempts of a post rate limited by the service.
+const ATTEMPTS: usize = 3;
+
+/// Post the new papers to every notifier, then as every bot.
+pub async fn notify(config: &Config, client: &Client, data: &ArxivRender) {
+    for (index, notifier) in config.notifiers.iter().enumerate() {
+        if let Err(err) = chat::post(config, notifier, index, client, data).await {
+            warn!("Cannot notify {:?} #{}: {}", notifier.service, index, err);
+        }
+    }
+    for (index, bot) in config.bots.iter().enumerate() {
+        if let Err(err) = bot::post(config, bot, index, client, data).await {
+            warn!("Cannot post as {:?} bot #{}: {}", bot.service, index, err);
+        }
+    }
+}
+
+/// A new paper once, with the sources listing it.
+#[derive(Serialize)]
+pub struct NewPaper<'a> {
+    #[serde(flatten)]
+    pub paper: &'a Arxiv,
+    pub sources: Vec<&'a str>,
+}
+
+/// The papers of each of the newest `days` passing `filter`, newest day first.
+pub fn new_days<'a>(
+    data: &'a ArxivRender,
+    days: usize,
+    filter: &PaperFilter,
+) -> Vec<(String, Vec<NewPaper<'a>>)> {
+    let sitemap = Sitemap::new(data);
+    sitemap
+        .dates
+        .iter()
+        .take(days)
+        .map(|(date, days)| {
+            let mut papers: IndexMap<&str, NewPaper> = IndexMap::new();
+            for category in days.iter().copied().flat_map(|d| &d.subjects) {
+                if !filter.sources.is_empty() && !filter.sources.contains(&category.subject) {
+                    continue;
+                }
+                for paper in category.papers.iter().filter(|p| selected(filter, p)) {
+                    let new = papers
+                        .entry(versionless_id(&paper.id))
+                        .or_insert_with(|| NewPaper {
+                            paper,
+                            sources: Vec::new(),
+                        });
+                    if !new.sources.contains(&category.subject.as_str()) {
+                        new.sources.push(&category.subject);
+                    }
+                }
+            }
+            (date.clone(), papers.into_values().collect())
+        })
+        .collect()
+}
+
+/// The papers of the newest `days` passing `filter`, once each, but those of `announced`, and
+/// the dates they span.
+pub fn new_papers<'a>(
+    data: &'a ArxivRender,
+    days: usize,
+    filter: &PaperFilter,
+    announced: &BTreeSet<String>,
+) -> (String, Vec<NewPaper<'a>>) {
+    let mut days = new_days(data, days, filter);
+    for (_, papers) in days.iter_mut() {
+        papers.retain(|p| !announced.contains(p.paper.versionless_id()));
+    }
+    days.retain(|(_, papers)| !papers.is_empty());
+    let dates = match (days.first(), days.last()) {
+        (Some((newest, _)), Some((oldest, _))) if newest != oldest => {
+            format!("{} to {}", oldest, newest)
+        }
+        (Some((newest, _)), _) => newest.clone(),
+        _ => String::new(),
+    };
+
+    let mut papers: IndexMap<&str, NewPaper> = IndexMap::new();
+    for new in days.into_iter().flat_map(|(_, papers)| papers) {
+        match papers.get_mut(new.paper.versionless_id()) {
+            Some(seen) => {
+                for source in new.sources {
+                    if !seen.sources.contains(&source) {
+                        seen.sources.push(source);
+                    }
+                }
+            }
+            None => {
+                papers.insert(new.paper.versionless_id(), new);
+            }
+        }
+    }
+    (dates, papers.into_values().collect())
+}
+
+fn selected(filter: &PaperFilter, paper: &Arxiv) -> bool {
+    if !filter.watched && filter.min_score.is_none() {
+        return true;
+    }
+    (filter.watched && !paper.watched.is_empty())
+        || filter
+            .min_score
+            .is_some_and(|min| paper.score.is_some_and(|score| score >= min))
+}
+
+/// The ledger at `path`, empty when there is none yet.
+pub fn load_ledger<T: DeserializeOwned + Default>(path: &str) -> Result<T> {
+    if !Path::new(path).exists() {
+        return Ok(T::default());
+    }
+    let text = fs::read_to_string(path)?;
+    serde_json::from_str(&text).map_err(|err| anyhow!("Cannot read ledger {}: {}", path, err))
+}
+
+/// Write to a temporary file renamed over the ledger, which a failed write never truncates.
+pub fn save_ledger<T: Serialize>(path: &str, ledger: &T) -> Result<()> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, serde_json::to_vec_pretty(ledger)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// `text` cut to `limit` characters, ellipsis included.
+pub fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut text: String = text.chars().take(limit.saturating_sub(1)).collect();
+    text.push('…');
+    text
+}
+
+/// Send `request`, waiting as long as the service asks when rate limited.
+pub async fn send(request: RequestBuilder) -> Result<Response> {
+    let mut attempt = 1;
+    loop {
+        let response = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("Cannot retry a streamed request"))?
+            .send()
+            .await?;
+        let status = response.status();
+        let url = response.url().clone();
+        if status == StatusCode::TOO_MANY_REQUESTS && attempt < ATTEMPTS {
+            let wait = response
+                .headers()
+                .get("retry-after")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 60.0);
+            warn!("{} is rate limited, retrying in {}s", url, wait);
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            attempt += 1;
+            continue;
+        }
+        if !status.is_success() {
+            bail!("{} returned {}: {}", url, status, response.text().await?);
+        }
+        return Ok(response);
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::core::Arxiv;
+use crate::v2::Sitemap;
+
+/// The papers of a day, by source.
+#[derive(Serialize)]
+pub struct DigestDay<'a> {
+    pub date: &'a str,
+    pub datetime: DateTime<Utc>,
+    pub subjects: Vec<DigestSection<'a>>,
+}
+
+/// The papers of a source on a day, over every update of the day.
+#[derive(Serialize)]
+pub struct DigestSection<'a> {
+    pub subject: &'a str,
+    pub hidden: usize,
+    pub papers: Vec<&'a Arxiv>,
+}
+
+/// The newest `days` of `sitemap`, every day when unset.
+pub fn days<'a>(sitemap: &'a Sitemap<'a>, days: Option<usize>) -> Vec<DigestDay<'a>> {
+    sitemap
+        .dates
+        .iter()
+        .take(days.unwrap_or(usize::MAX))
+        .map(|(date, days)| {
+            let mut subjects: IndexMap<&str, DigestSection> = IndexMap::new();
+            for category in days.iter().flat_map(|d| &d.subjects) {
+                let section = subjects
+                    .entry(&category.subject)
+                    .or_insert_with(|| DigestSection {
+                        subject: &category.subject,
+                        hidden: 0,
+                        papers: Vec::new(),
+                    });
+                section.hidden += category.hidden;
+                section.papers.extend(&category.papers);
+            }
+            DigestDay {
+                date,
+                datetime: days[0].datetime,
+                subjects: subjects.into_values().collect(),
+            }
+        })
+        .collect()
+}
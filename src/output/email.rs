@@ -0,0 +1,203 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::digest::{self, DigestDay};
+use super::feed::one_line;
+use super::Renderer;
+use crate::config::{Config, EmailOutput, Smtp, SmtpTls};
+use crate::utils::env_var;
+use crate::v1::{with_templates, ArxivRender};
+use crate::v2::Sitemap;
+
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "html",
+        include_str!("../../vendor/system-templates/email/html.hbs"),
+    ),
+    (
+        "text",
+        include_str!("../../vendor/system-templates/email/text.hbs"),
+    ),
+];
+
+/// A daily or weekly digest by email, as HTML with inline styles and as plain text.
+pub struct Email<'a> {
+    output: &'a EmailOutput,
+    target_dir: &'a str,
+    sent_file: String,
+}
+
+impl<'a> Email<'a> {
+    pub fn new(config: &'a Config, output: &'a EmailOutput) -> Email<'a> {
+        let sent_file = output.sent_file.clone().unwrap_or_else(|| {
+            let mut to = output.to.clone();
+            to.sort();
+            let server = match &output.smtp {
+                Some(smtp) => format!("smtp:{}:{}", smtp.host, smtp.port.unwrap_or_default()),
+                None => format!("eml:{}", output.eml_dir),
+            };
+            config.ledger_path("email", &[&output.from, &to.join(","), &server], "txt")
+        });
+        Email {
+            output,
+            target_dir: output.target_dir.as_deref().unwrap_or(&config.target_dir),
+            sent_file,
+        }
+    }
+
+    /// The newest date of the last digest sent, if any.
+    fn last_sent(&self) -> Result<Option<String>> {
+        if !Path::new(&self.sent_file).exists() {
+            return Ok(None);
+        }
+        let date = fs::read_to_string(&self.sent_file)?;
+        Ok(Some(date.trim().to_string()).filter(|d| !d.is_empty()))
+    }
+
+    fn mark_sent(&self, newest: &str) -> Result<()> {
+        if let Some(dir) = Path::new(&self.sent_file).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.sent_file, format!("{}\n", newest))?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct Digest<'a> {
+    site_title: &'a str,
+    build_time: DateTime<Utc>,
+    project_name: &'static str,
+    project_version: &'static str,
+    project_homepage: &'static str,
+    base_url: Option<&'a str>,
+    /// The date of the digest, or its first and last dates when it spans several days.
+    dates: String,
+    /// Papers in the digest, each counted once.
+    papers: usize,
+    days: Vec<DigestDay<'a>>,
+}
+
+impl Renderer for Email<'_> {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn render(&self, config: &Config, hbs: &Handlebars<'static>, data: &ArxivRender) -> Result<()> {
+        if self.output.to.is_empty() {
+            bail!("The email output has no recipients in `to`");
+        }
+        let sitemap = Sitemap::new(data);
+        let days = digest::days(&sitemap, Some(self.output.days));
+        let papers = days
+            .iter()
+            .flat_map(|d| &d.subjects)
+            .flat_map(|s| &s.papers)
+            .map(|p| p.versionless_id())
+            .collect::<HashSet<_>>()
+            .len();
+        let (newest, oldest) = match (days.first(), days.last()) {
+            (Some(newest), Some(oldest)) if papers > 0 => (newest.date, oldest.date),
+            _ => {
+                info!("No papers to send");
+                return Ok(());
+            }
+        };
+        // Wait until every day is newer than the last digest, so that `days = 7` sends one a week.
+        if let Some(last) = self.last_sent()? {
+            if oldest <= last.as_str() {
+                info!("Digest up to {} already sent, waiting for newer days", last);
+                return Ok(());
+            }
+        }
+        let dates = if newest == oldest {
+            newest.to_string()
+        } else {
+            format!("{} to {}", oldest, newest)
+        };
+
+        let digest = Digest {
+            site_title: &data.site_title,
+            build_time: data.build_time,
+            project_name: data.project_name,
+            project_version: data.project_version,
+            project_homepage: data.project_homepage,
+            base_url: config.base_url.as_deref(),
+            dates,
+            papers,
+            days,
+        };
+        let mut hbs = with_templates(hbs, TEMPLATES, &self.output.templates_dir)?;
+        let html = hbs.render("html", &digest)?;
+        hbs.register_escape_fn(one_line);
+        let text = hbs.render("text", &digest)?;
+        let subject = hbs.render_template(&self.output.subject, &digest)?;
+        let message = message(self.output, subject, text, html)?;
+
+        match &self.output.smtp {
+            Some(smtp) => {
+                // Like the notifiers, a failed send does not fail the build, and is retried next
+                // build since the digest is not marked sent.
+                if let Err(err) = transport(smtp).and_then(|t| Ok(t.send(&message)?)) {
+                    warn!("Cannot send the digest via {}: {}", smtp.host, err);
+                    return Ok(());
+                }
+                info!(
+                    "Digest sent to {} recipients via {}",
+                    self.output.to.len(),
+                    smtp.host
+                );
+            }
+            None => {
+                let dir = Path::new(self.target_dir).join(&self.output.eml_dir);
+                fs::create_dir_all(&dir)?;
+                let path = dir.join(format!("{}.eml", newest));
+                fs::write(&path, message.formatted())?;
+                info!("{} generated", path.to_string_lossy());
+            }
+        }
+        self.mark_sent(newest)
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|err| anyhow!("Invalid email address {}: {}", address, err))
+}
+
+fn message(output: &EmailOutput, subject: String, text: String, html: String) -> Result<Message> {
+    let mut builder = Message::builder()
+        .from(mailbox(&output.from)?)
+        .subject(subject);
+    for to in &output.to {
+        builder = builder.to(mailbox(to)?);
+    }
+    Ok(builder.multipart(MultiPart::alternative_plain_html(text, html))?)
+}
+
+fn transport(smtp: &Smtp) -> Result<SmtpTransport> {
+    let mut builder = match smtp.tls {
+        SmtpTls::None => SmtpTransport::builder_dangerous(&smtp.host),
+        SmtpTls::Starttls => SmtpTransport::starttls_relay(&smtp.host)?,
+        SmtpTls::Tls => SmtpTransport::relay(&smtp.host)?,
+    };
+    if let Some(port) = smtp.port {
+        builder = builder.port(port);
+    }
+    if let Some(username) = &smtp.username {
+        let password = env_var(smtp.password_env.as_deref())?
+            .ok_or_else(|| anyhow!("No `password_env` for the SMTP username {}", username))?;
+        builder = builder.credentials(Credentials::new(username.clone(), password));
+    }
+    Ok(builder.build())
+}
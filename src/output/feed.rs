@@ -0,0 +1,295 @@
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use handlebars::Handlebars;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use super::Renderer;
+use crate::config::{Config, FeedOutput};
+use crate::core::{versionless_id, Arxiv};
+use crate::utils::unique_slug;
+use crate::v1::ArxivRender;
+use crate::{crate_homepage, crate_name, crate_version};
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+
+/// RSS 2.0 and Atom feeds of the papers, overall and per source.
+pub struct Feed<'a> {
+    output: &'a FeedOutput,
+    target_dir: &'a str,
+}
+
+impl<'a> Feed<'a> {
+    pub fn new(config: &'a Config, output: &'a FeedOutput) -> Feed<'a> {
+        Feed {
+            output,
+            target_dir: output.target_dir.as_deref().unwrap_or(&config.target_dir),
+        }
+    }
+}
+
+/// A paper once, with the sources listing it.
+pub struct Entry<'a> {
+    pub paper: &'a Arxiv,
+    pub subjects: Vec<&'a str>,
+}
+
+/// Every paper once, newest day first and in section order within a day.
+pub fn entries(data: &ArxivRender) -> IndexMap<&str, Entry<'_>> {
+    let mut entries: IndexMap<&str, Entry> = IndexMap::new();
+    for category in data.days.iter().flat_map(|d| &d.subjects) {
+        for paper in &category.papers {
+            let entry = entries
+                .entry(versionless_id(&paper.id))
+                .or_insert_with(|| Entry {
+                    paper,
+                    subjects: Vec::new(),
+                });
+            if !entry.subjects.contains(&category.subject.as_str()) {
+                entry.subjects.push(&category.subject);
+            }
+        }
+    }
+    entries
+}
+
+/// The sources of `data` with their slugs, as the pages of `V2` name them. `all` is taken by the
+/// feeds of every source.
+pub fn sources(data: &ArxivRender) -> Vec<(&str, String)> {
+    let mut slugs = vec!["all".to_string()];
+    let mut sources: Vec<(&str, String)> = Vec::new();
+    for category in data.days.iter().flat_map(|d| &d.subjects) {
+        if sources
+            .iter()
+            .all(|(subject, _)| *subject != category.subject)
+        {
+            let slug = unique_slug(&category.subject, &mut slugs);
+            sources.push((&category.subject, slug));
+        }
+    }
+    sources
+}
+
+impl Renderer for Feed<'_> {
+    fn name(&self) -> &str {
+        "feed"
+    }
+
+    fn render(&self, config: &Config, _: &Handlebars<'static>, data: &ArxivRender) -> Result<()> {
+        let dir = Path::new(self.target_dir).join(&self.output.dir);
+        fs::create_dir_all(&dir)?;
+        let entries = entries(data);
+
+        let mut feeds = vec![(config.site_title.clone(), "all".to_string(), None)];
+        if self.output.per_source {
+            for (subject, slug) in sources(data) {
+                let title = format!("{} - {}", config.site_title, subject);
+                feeds.push((title, slug, Some(subject)));
+            }
+        }
+
+        for (title, name, subject) in &feeds {
+            let papers: Vec<&Entry> = entries
+                .values()
+                .filter(|e| subject.is_none_or(|s| e.subjects.contains(&s)))
+                .take(self.output.max_items)
+                .collect();
+            let meta = FeedMeta {
+                title,
+                base_url: config
+                    .base_url
+                    .as_deref()
+                    .map(|url| url.trim_end_matches('/')),
+                build_time: data.build_time,
+            };
+            if self.output.rss {
+                let path = format!("{}/{}.rss.xml", self.output.dir, name);
+                fs::write(
+                    Path::new(self.target_dir).join(&path),
+                    rss(&meta, &path, &papers)?,
+                )?;
+            }
+            if self.output.atom {
+                let path = format!("{}/{}.atom.xml", self.output.dir, name);
+                fs::write(
+                    Path::new(self.target_dir).join(&path),
+                    atom(&meta, &path, &papers)?,
+                )?;
+            }
+        }
+        info!(
+            "{} feeds generated in {}",
+            feeds.len(),
+            dir.to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+struct FeedMeta<'a> {
+    title: &'a str,
+    base_url: Option<&'a str>,
+    build_time: DateTime<Utc>,
+}
+
+impl FeedMeta<'_> {
+    /// Absolute URL of `path` under `base_url`.
+    fn url(&self, path: &str) -> Option<String> {
+        self.base_url.map(|base| format!("{}/{}", base, path))
+    }
+
+    fn home(&self) -> String {
+        self.url("")
+            .unwrap_or_else(|| crate_homepage!().to_string())
+    }
+}
+
+/// A GUID and ID stable across versions: the versionless abstract URL.
+pub fn guid(paper: &Arxiv) -> String {
+    format!("https://arxiv.org/abs/{}", paper.versionless_id())
+}
+
+/// Titles and abstracts from arXiv are wrapped, join their lines.
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn description(paper: &Arxiv) -> String {
+    match &paper.tldr {
+        Some(tldr) => format!("TL;DR: {}\n\n{}", one_line(tldr), one_line(&paper.summary)),
+        None => one_line(&paper.summary),
+    }
+}
+
+fn writer() -> EventWriter<Vec<u8>> {
+    EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(Vec::new())
+}
+
+fn text_element(w: &mut EventWriter<Vec<u8>>, name: &str, text: &str) -> Result<()> {
+    w.write(XmlEvent::start_element(name))?;
+    w.write(XmlEvent::characters(text))?;
+    w.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+fn rss(meta: &FeedMeta, path: &str, papers: &[&Entry]) -> Result<Vec<u8>> {
+    let mut w = writer();
+    w.write(
+        XmlEvent::start_element("rss")
+            .attr("version", "2.0")
+            .ns("atom", ATOM_NS)
+            .ns("dc", DC_NS),
+    )?;
+    w.write(XmlEvent::start_element("channel"))?;
+    text_element(&mut w, "title", meta.title)?;
+    text_element(&mut w, "link", &meta.home())?;
+    text_element(&mut w, "description", meta.title)?;
+    if let Some(url) = meta.url(path) {
+        w.write(
+            XmlEvent::start_element("atom:link")
+                .attr("href", &url)
+                .attr("rel", "self")
+                .attr("type", "application/rss+xml"),
+        )?;
+        w.write(XmlEvent::end_element())?;
+    }
+    text_element(&mut w, "lastBuildDate", &meta.build_time.to_rfc2822())?;
+    let generator = format!("{} {}", crate_name!(), crate_version!());
+    text_element(&mut w, "generator", &generator)?;
+
+    for entry in papers {
+        let paper = entry.paper;
+        w.write(XmlEvent::start_element("item"))?;
+        text_element(&mut w, "title", &one_line(&paper.title))?;
+        text_element(&mut w, "link", &paper.id)?;
+        w.write(XmlEvent::start_element("guid").attr("isPermaLink", "true"))?;
+        w.write(XmlEvent::characters(&guid(paper)))?;
+        w.write(XmlEvent::end_element())?;
+        text_element(&mut w, "pubDate", &paper.updated.to_rfc2822())?;
+        for author in &paper.authors {
+            text_element(&mut w, "dc:creator", &author.canonical)?;
+        }
+        for subject in &entry.subjects {
+            text_element(&mut w, "category", subject)?;
+        }
+        text_element(&mut w, "description", &description(paper))?;
+        w.write(XmlEvent::end_element())?;
+    }
+
+    w.write(XmlEvent::end_element())?;
+    w.write(XmlEvent::end_element())?;
+    Ok(w.into_inner())
+}
+
+fn atom(meta: &FeedMeta, path: &str, papers: &[&Entry]) -> Result<Vec<u8>> {
+    let timestamp = |t: &DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let updated = papers
+        .iter()
+        .map(|e| e.paper.updated)
+        .max()
+        .unwrap_or(meta.build_time);
+
+    let mut w = writer();
+    w.write(XmlEvent::start_element("feed").default_ns(ATOM_NS))?;
+    let id = meta
+        .url(path)
+        .unwrap_or_else(|| format!("urn:{}:{}", crate_name!(), path));
+    text_element(&mut w, "id", &id)?;
+    text_element(&mut w, "title", meta.title)?;
+    text_element(&mut w, "updated", &timestamp(&updated))?;
+    if let Some(url) = meta.url(path) {
+        w.write(
+            XmlEvent::start_element("link")
+                .attr("rel", "self")
+                .attr("href", &url),
+        )?;
+        w.write(XmlEvent::end_element())?;
+    }
+    w.write(XmlEvent::start_element("link").attr("href", &meta.home()))?;
+    w.write(XmlEvent::end_element())?;
+    w.write(
+        XmlEvent::start_element("generator")
+            .attr("uri", crate_homepage!())
+            .attr("version", crate_version!()),
+    )?;
+    w.write(XmlEvent::characters(crate_name!()))?;
+    w.write(XmlEvent::end_element())?;
+
+    for entry in papers {
+        let paper = entry.paper;
+        w.write(XmlEvent::start_element("entry"))?;
+        text_element(&mut w, "id", &guid(paper))?;
+        text_element(&mut w, "title", &one_line(&paper.title))?;
+        text_element(&mut w, "updated", &timestamp(&paper.updated))?;
+        text_element(&mut w, "published", &timestamp(&paper.published))?;
+        w.write(XmlEvent::start_element("link").attr("href", &paper.id))?;
+        w.write(XmlEvent::end_element())?;
+        w.write(
+            XmlEvent::start_element("link")
+                .attr("rel", "related")
+                .attr("type", "application/pdf")
+                .attr("href", &paper.pdf_url),
+        )?;
+        w.write(XmlEvent::end_element())?;
+        for author in &paper.authors {
+            w.write(XmlEvent::start_element("author"))?;
+            text_element(&mut w, "name", &author.canonical)?;
+            w.write(XmlEvent::end_element())?;
+        }
+        for subject in &entry.subjects {
+            w.write(XmlEvent::start_element("category").attr("term", subject))?;
+            w.write(XmlEvent::end_element())?;
+        }
+        text_element(&mut w, "summary", &description(paper))?;
+        w.write(XmlEvent::end_element())?;
+    }
+
+    w.write(XmlEvent::end_element())?;
+    Ok(w.into_inner())
+}
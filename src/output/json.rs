@@ -0,0 +1,398 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use super::feed::{entries, guid, one_line};
+use super::Renderer;
+use crate::config::{Config, JsonOutput};
+use crate::core::{versionless_id, Arxiv};
+use crate::v1::ArxivRender;
+use crate::v2::{paper_slug, Sitemap};
+use crate::{crate_name, crate_version};
+
+/// Version of the API documents, raised on incompatible changes.
+const API_VERSION: u32 = 1;
+
+/// A JSON Feed 1.1 and a static API of days, sources and papers.
+pub struct Json<'a> {
+    output: &'a JsonOutput,
+    target_dir: &'a str,
+}
+
+impl<'a> Json<'a> {
+    pub fn new(config: &'a Config, output: &'a JsonOutput) -> Json<'a> {
+        Json {
+            output,
+            target_dir: output.target_dir.as_deref().unwrap_or(&config.target_dir),
+        }
+    }
+}
+
+impl Renderer for Json<'_> {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, config: &Config, _: &Handlebars<'static>, data: &ArxivRender) -> Result<()> {
+        let target_dir = Path::new(self.target_dir);
+        if !self.output.feed.is_empty() {
+            let path = target_dir.join(&self.output.feed);
+            write_json(
+                &path,
+                &json_feed(config, &self.output.feed, self.output.max_items, data),
+            )?;
+            info!("{} generated", path.to_string_lossy());
+        }
+        if !self.output.api_dir.is_empty() {
+            let dir = target_dir.join(&self.output.api_dir);
+            let documents = api(&dir, data)?;
+            info!(
+                "{} API documents generated in {}",
+                documents,
+                dir.to_string_lossy()
+            );
+        }
+        Ok(())
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(value)?)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    home_page_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feed_url: Option<String>,
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: String,
+    url: &'a str,
+    external_url: &'a str,
+    title: String,
+    content_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    date_published: DateTime<Utc>,
+    date_modified: DateTime<Utc>,
+    authors: Vec<JsonFeedAuthor<'a>>,
+    tags: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor<'a> {
+    name: &'a str,
+}
+
+fn json_feed<'a>(
+    config: &'a Config,
+    path: &str,
+    max_items: usize,
+    data: &'a ArxivRender,
+) -> JsonFeed<'a> {
+    let base_url = config
+        .base_url
+        .as_deref()
+        .map(|url| url.trim_end_matches('/'));
+    let items = entries(data)
+        .into_values()
+        .take(max_items)
+        .map(|entry| JsonFeedItem {
+            id: guid(entry.paper),
+            url: &entry.paper.id,
+            external_url: &entry.paper.pdf_url,
+            title: one_line(&entry.paper.title),
+            content_text: one_line(&entry.paper.summary),
+            summary: entry.paper.tldr.as_deref().map(one_line),
+            date_published: entry.paper.published,
+            date_modified: entry.paper.updated,
+            authors: entry
+                .paper
+                .authors
+                .iter()
+                .map(|a| JsonFeedAuthor { name: &a.canonical })
+                .collect(),
+            tags: entry.subjects,
+        })
+        .collect();
+    JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: &config.site_title,
+        home_page_url: base_url.map(|base| format!("{}/", base)),
+        feed_url: base_url.map(|base| format!("{}/{}", base, path)),
+        items,
+    }
+}
+
+/// A paper in API documents.
+#[derive(Serialize)]
+struct Paper<'a> {
+    /// The versionless arXiv ID, e.g. `2401.00001`.
+    id: &'a str,
+    /// The arXiv ID of this version, e.g. `2401.00001v2`.
+    version: &'a str,
+    url: &'a str,
+    pdf_url: &'a str,
+    title: String,
+    summary: String,
+    authors: Vec<PaperAuthor<'a>>,
+    comment: Option<&'a str>,
+    published: DateTime<Utc>,
+    updated: DateTime<Utc>,
+    tldr: Option<&'a str>,
+    translations: &'a BTreeMap<String, String>,
+    score: Option<f64>,
+    matched_terms: &'a [String],
+    watched: &'a [String],
+    affinity: Option<f64>,
+    related: Vec<PaperLink<'a>>,
+}
+
+#[derive(Serialize)]
+struct PaperAuthor<'a> {
+    name: &'a str,
+    canonical: &'a str,
+    affiliation: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct PaperLink<'a> {
+    id: &'a str,
+    title: String,
+    similarity: f64,
+}
+
+impl<'a> Paper<'a> {
+    fn new(paper: &'a Arxiv) -> Paper<'a> {
+        Paper {
+            id: paper.versionless_id(),
+            version: paper.versioned_id(),
+            url: &paper.id,
+            pdf_url: &paper.pdf_url,
+            title: one_line(&paper.title),
+            summary: one_line(&paper.summary),
+            authors: paper
+                .authors
+                .iter()
+                .map(|a| PaperAuthor {
+                    name: &a.name,
+                    canonical: &a.canonical,
+                    affiliation: a.affiliation.as_deref(),
+                })
+                .collect(),
+            comment: paper.comment.as_deref(),
+            published: paper.published,
+            updated: paper.updated,
+            tldr: paper.tldr.as_deref(),
+            translations: &paper.translations,
+            score: paper.score,
+            matched_terms: &paper.matched_terms,
+            watched: &paper.watched,
+            affinity: paper.affinity,
+            related: paper
+                .related
+                .iter()
+                .map(|r| PaperLink {
+                    id: versionless_id(&r.id),
+                    title: one_line(&r.title),
+                    similarity: r.similarity,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Index<'a> {
+    api_version: u32,
+    site_title: &'a str,
+    build_time: DateTime<Utc>,
+    generator: String,
+    days: Vec<IndexLink<'a>>,
+    sources: Vec<IndexLink<'a>>,
+}
+
+/// A day or source, with the path of its document from the API directory.
+#[derive(Serialize)]
+struct IndexLink<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slug: Option<&'a str>,
+    papers: usize,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct Day<'a> {
+    api_version: u32,
+    date: &'a str,
+    sections: Vec<Section<'a>>,
+}
+
+#[derive(Serialize)]
+struct Section<'a> {
+    source: &'a str,
+    slug: &'a str,
+    hidden: usize,
+    papers: Vec<Paper<'a>>,
+    /// Topics of the section, by the versionless IDs of their papers.
+    topics: Vec<Topic<'a>>,
+}
+
+#[derive(Serialize)]
+struct Topic<'a> {
+    label: &'a str,
+    terms: &'a [String],
+    papers: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Source<'a> {
+    api_version: u32,
+    source: &'a str,
+    slug: &'a str,
+    hidden: usize,
+    days: Vec<SourceDay<'a>>,
+}
+
+#[derive(Serialize)]
+struct SourceDay<'a> {
+    date: &'a str,
+    papers: Vec<Paper<'a>>,
+}
+
+#[derive(Serialize)]
+struct PaperDocument<'a> {
+    api_version: u32,
+    #[serde(flatten)]
+    paper: Paper<'a>,
+    /// Slugs of the sources listing the paper.
+    sources: Vec<&'a str>,
+    dates: Vec<&'a str>,
+}
+
+/// Write the API documents in `dir`, returning how many.
+fn api(dir: &Path, data: &ArxivRender) -> Result<usize> {
+    let sitemap = Sitemap::new(data);
+    let site = &sitemap.site;
+    let slugs: IndexMap<&str, &str> = site
+        .sources
+        .iter()
+        .map(|s| (s.subject.as_str(), s.slug.as_str()))
+        .collect();
+
+    let index = Index {
+        api_version: API_VERSION,
+        site_title: site.site_title,
+        build_time: site.build_time,
+        generator: format!("{} {}", crate_name!(), crate_version!()),
+        days: site
+            .days
+            .iter()
+            .map(|d| IndexLink {
+                date: Some(&d.date),
+                source: None,
+                slug: None,
+                papers: d.papers,
+                url: format!("days/{}.json", d.date),
+            })
+            .collect(),
+        sources: site
+            .sources
+            .iter()
+            .map(|s| IndexLink {
+                date: None,
+                source: Some(&s.subject),
+                slug: Some(&s.slug),
+                papers: s.papers,
+                url: format!("sources/{}.json", s.slug),
+            })
+            .collect(),
+    };
+    write_json(&dir.join("index.json"), &index)?;
+
+    for (date, days) in &sitemap.dates {
+        let mut sections: IndexMap<&str, Section> = IndexMap::new();
+        for category in days.iter().flat_map(|d| &d.subjects) {
+            let section = sections
+                .entry(&category.subject)
+                .or_insert_with(|| Section {
+                    source: &category.subject,
+                    slug: slugs[category.subject.as_str()],
+                    hidden: 0,
+                    papers: Vec::new(),
+                    topics: Vec::new(),
+                });
+            section.hidden += category.hidden;
+            section
+                .papers
+                .extend(category.papers.iter().map(Paper::new));
+            section.topics.extend(category.topics.iter().map(|t| Topic {
+                label: &t.label,
+                terms: &t.terms,
+                papers: t.papers.iter().map(|p| p.versionless_id()).collect(),
+            }));
+        }
+        let day = Day {
+            api_version: API_VERSION,
+            date,
+            sections: sections.into_values().collect(),
+        };
+        write_json(&dir.join(format!("days/{}.json", date)), &day)?;
+    }
+
+    for source in sitemap.sources.values() {
+        let slug = slugs[source.subject];
+        let document = Source {
+            api_version: API_VERSION,
+            source: source.subject,
+            slug,
+            hidden: source.hidden,
+            days: source
+                .days
+                .iter()
+                .map(|d| SourceDay {
+                    date: &d.date,
+                    papers: d.papers.iter().map(|p| Paper::new(p)).collect(),
+                })
+                .collect(),
+        };
+        write_json(&dir.join(format!("sources/{}.json", slug)), &document)?;
+    }
+
+    for (paper, sources, dates) in sitemap.papers.values() {
+        let document = PaperDocument {
+            api_version: API_VERSION,
+            paper: Paper::new(paper),
+            sources: sources
+                .iter()
+                .map(|&i| site.sources[i].slug.as_str())
+                .collect(),
+            dates: dates.iter().map(|&i| site.days[i].date.as_str()).collect(),
+        };
+        let path = dir.join(format!("papers/{}.json", paper_slug(&paper.id)));
+        write_json(&path, &document)?;
+    }
+
+    Ok(1 + sitemap.dates.len() + sitemap.sources.len() + sitemap.papers.len())
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use handlebars::Handlebars;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use super::digest::{self, DigestDay};
+use super::Renderer;
+use crate::config::{Config, MarkdownOutput, MarkdownStyle};
+use crate::v1::{segments, with_templates, ArxivRender, Segment};
+use crate::v2::Sitemap;
+
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "digest",
+        include_str!("../../vendor/system-templates/markdown/digest.hbs"),
+    ),
+    (
+        "paper",
+        include_str!("../../vendor/system-templates/markdown/paper.hbs"),
+    ),
+    (
+        "table",
+        include_str!("../../vendor/system-templates/markdown/table.hbs"),
+    ),
+];
+
+/// A GitHub-flavoured Markdown digest of the papers, by day and source.
+pub struct Markdown<'a> {
+    output: &'a MarkdownOutput,
+    target_dir: &'a str,
+}
+
+impl<'a> Markdown<'a> {
+    pub fn new(config: &'a Config, output: &'a MarkdownOutput) -> Markdown<'a> {
+        Markdown {
+            output,
+            target_dir: output.target_dir.as_deref().unwrap_or(&config.target_dir),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Digest<'a> {
+    site_title: &'a str,
+    build_time: DateTime<Utc>,
+    project_name: &'static str,
+    project_version: &'static str,
+    project_homepage: &'static str,
+    style: MarkdownStyle,
+    days: Vec<DigestDay<'a>>,
+}
+
+impl Renderer for Markdown<'_> {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn render(&self, _: &Config, hbs: &Handlebars<'static>, data: &ArxivRender) -> Result<()> {
+        let mut hbs = with_templates(hbs, TEMPLATES, &self.output.templates_dir)?;
+        hbs.register_escape_fn(escape_markdown);
+
+        let sitemap = Sitemap::new(data);
+        let days = digest::days(&sitemap, self.output.days);
+        let digest = Digest {
+            site_title: &data.site_title,
+            build_time: data.build_time,
+            project_name: data.project_name,
+            project_version: data.project_version,
+            project_homepage: data.project_homepage,
+            style: self.output.style,
+            days,
+        };
+
+        fs::create_dir_all(self.target_dir)?;
+        let path = Path::new(self.target_dir).join(&self.output.file);
+        fs::write(&path, hbs.render("digest", &digest)?)?;
+        info!("{} generated", path.to_string_lossy());
+        Ok(())
+    }
+}
+
+/// Characters Markdown may take as formatting, escaped wherever they are.
+const MARKUP: &[char] = &[
+    '\\', '`', '*', '_', '~', '&', '[', ']', '<', '>', '|', '#', '$',
+];
+
+/// Keep text on one line, so it fits in lists and tables, and escape the characters Markdown would
+/// take as formatting, including a leading list marker. Math is written as `$...$` or `$$...$$`,
+/// which GitHub renders.
+fn escape_markdown(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut escaped = String::with_capacity(text.len());
+    for segment in segments(&text) {
+        match segment {
+            Segment::Text(text) => {
+                let marker = if escaped.is_empty() {
+                    list_marker(text)
+                } else {
+                    None
+                };
+                for (i, c) in text.char_indices() {
+                    if Some(i) == marker || MARKUP.contains(&c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+            }
+            Segment::Math { tex, display, .. } => {
+                let delimiter = if display { "$$" } else { "$" };
+                escaped.push_str(delimiter);
+                escaped.push_str(&tex.replace('|', "\\|"));
+                escaped.push_str(delimiter);
+            }
+        }
+    }
+    escaped
+}
+
+/// The index of the character that makes `text` start a list, as in `- `, `+ ` or `1. `.
+fn list_marker(text: &str) -> Option<usize> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match text[digits..].chars().next() {
+        Some('-' | '+') if digits == 0 => Some(0),
+        Some('.' | ')') if digits > 0 => Some(digits),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_and_empty_text() {
+        assert_eq!(escape_markdown(""), "");
+        assert_eq!(escape_markdown("   "), "");
+        assert_eq!(
+            escape_markdown("Graph neural networks, 2nd edition!"),
+            "Graph neural networks, 2nd edition!"
+        );
+        assert_eq!(
+            escape_markdown(" Attention\n  is\tall you\r\nneed "),
+            "Attention is all you need"
+        );
+    }
+
+    #[test]
+    fn markup_characters() {
+        assert_eq!(
+            escape_markdown("A *bold* [link](x) with `code` and <b>tags</b>"),
+            r"A \*bold\* \[link\](x) with \`code\` and \<b\>tags\</b\>"
+        );
+        assert_eq!(escape_markdown("a | b # c ~~d~~"), r"a \| b \# c \~\~d\~\~");
+        assert_eq!(
+            escape_markdown("snake_case & C:\\dir"),
+            r"snake\_case \& C:\\dir"
+        );
+        assert_eq!(escape_markdown("&amp;"), r"\&amp;");
+    }
+
+    #[test]
+    fn leading_list_markers() {
+        assert_eq!(escape_markdown("- not a list"), r"\- not a list");
+        assert_eq!(escape_markdown("+1 for this"), r"\+1 for this");
+        assert_eq!(escape_markdown("2024. A year"), r"2024\. A year");
+        assert_eq!(escape_markdown("1) First"), r"1\) First");
+        assert_eq!(escape_markdown("GPT-4 and 3.5"), "GPT-4 and 3.5");
+        assert_eq!(escape_markdown("2024 results"), "2024 results");
+    }
+
+    #[test]
+    fn math_is_kept() {
+        assert_eq!(
+            escape_markdown("Bound $|x|_2 < 1$ here"),
+            r"Bound $\|x\|_2 < 1$ here"
+        );
+        assert_eq!(escape_markdown("$$a*b$$"), "$$a*b$$");
+        assert_eq!(escape_markdown("costs 5$"), r"costs 5\$");
+    }
+}
@@ -0,0 +1,92 @@
+mod digest;
+mod email;
+mod feed;
+mod json;
+mod markdown;
+
+pub(crate) use feed::{guid, one_line};
+
+use anyhow::Result;
+use handlebars::Handlebars;
+use tracing::info;
+
+use crate::config::{Config, Output, SiteOutput, Version};
+use crate::core::{Arxiv, ArxivCollection, AuthorAliases};
+use crate::stages;
+use crate::v1::{self, ArxivRender};
+use crate::v2;
+
+/// Writes the artifacts of one output from the prepared days.
+pub trait Renderer {
+    /// Name of the output, for messages.
+    fn name(&self) -> &str;
+
+    /// `hbs` is the engine shared by the outputs, with the helpers and scripts but no templates.
+    fn render(&self, config: &Config, hbs: &Handlebars<'static>, data: &ArxivRender) -> Result<()>;
+}
+
+/// The renderers of `[[outputs]]`, or of `version` when none is listed.
+pub fn renderers(config: &Config) -> Vec<Box<dyn Renderer + '_>> {
+    if config.outputs.is_empty() {
+        // The only site, so `target_dir` whatever its version.
+        let output = SiteOutput {
+            target_dir: Some(config.target_dir.clone()),
+            ..Default::default()
+        };
+        return match config.version {
+            Version::V1 => vec![Box::new(v1::Site::new(config, &output))],
+            Version::V2 => vec![Box::new(v2::Site::new(config, &output))],
+        };
+    }
+    config
+        .outputs
+        .iter()
+        .map(|output| -> Box<dyn Renderer> {
+            match output {
+                Output::V1(output) => Box::new(v1::Site::new(config, output)),
+                Output::V2(output) => Box::new(v2::Site::new(config, output)),
+                Output::Feed(output) => Box::new(feed::Feed::new(config, output)),
+                Output::Json(output) => Box::new(json::Json::new(config, output)),
+                Output::Markdown(output) => Box::new(markdown::Markdown::new(config, output)),
+                Output::Email(output) => Box::new(email::Email::new(config, output)),
+            }
+        })
+        .collect()
+}
+
+/// Run the stages once, then every output on their result, which is returned for the notifiers.
+pub fn render(config: &Config, raw_data: ArxivCollection, seeds: &[Arxiv]) -> Result<ArxivRender> {
+    let renderers = renderers(config);
+    let data = prepare(config, raw_data, seeds)?;
+    let hbs = v1::handlebars(config)?;
+    for renderer in &renderers {
+        info!("Rendering output: {}", renderer.name());
+        renderer.render(config, &hbs, &data)?;
+    }
+    Ok(data)
+}
+
+/// Group the papers by day and run the stages, from author resolution to clustering.
+fn prepare(config: &Config, raw_data: ArxivCollection, seeds: &[Arxiv]) -> Result<ArxivRender> {
+    let mut render_data = ArxivRender::new(config.site_title.clone(), raw_data);
+    render_data.sort();
+    let aliases = AuthorAliases::new(&config.author_aliases);
+    stages::resolve_authors(&aliases, &mut render_data.days);
+    stages::mute(&config.mute, &aliases, &mut render_data.days)?;
+    stages::watch(
+        &config.authors,
+        &aliases,
+        &config.watched_title,
+        &mut render_data.days,
+    );
+    stages::score(&config.scoring, &mut render_data.days)?;
+    stages::recommend(
+        &config.recommend,
+        seeds,
+        &config.watched_title,
+        &mut render_data.days,
+    );
+    stages::related(&config.related, &mut render_data.days);
+    stages::cluster(&config.clustering, &mut render_data.days);
+    Ok(render_data)
+}
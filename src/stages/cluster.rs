@@ -0,0 +1,262 @@
+use super::tfidf::{cosine, normalize, paper_terms, TermVector, TfIdf};
+use crate::config::Clustering;
+use crate::core::{Arxiv, ArxivDaily, ArxivTopic};
+use crate::text;
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Group the papers of each large enough category into topics labelled by their top terms.
+pub fn cluster(config: &Clustering, days: &mut [ArxivDaily]) {
+    if !config.enabled || config.max_topics < 2 {
+        return;
+    }
+
+    let mut terms: HashMap<String, Vec<String>> = HashMap::new();
+    for paper in days
+        .iter()
+        .flat_map(|d| &d.subjects)
+        .flat_map(|s| &s.papers)
+    {
+        terms
+            .entry(paper.versionless_id().to_string())
+            .or_insert_with(|| paper_terms(paper));
+    }
+    let tfidf = TfIdf::fit(terms.values());
+
+    let mut clustered = 0;
+    for category in days.iter_mut().flat_map(|d| &mut d.subjects) {
+        let n = category.papers.len();
+        let k = n
+            .div_ceil(config.papers_per_topic.max(1))
+            .min(config.max_topics);
+        if n < config.min_papers || k < 2 {
+            continue;
+        }
+        let vectors: Vec<TermVector> = category
+            .papers
+            .iter()
+            .map(|p| tfidf.vector(&terms[p.versionless_id()]))
+            .collect();
+        let assignment = kmeans(&vectors, k, config.iterations);
+
+        let mut groups: Vec<Vec<usize>> = vec![Vec::new(); k];
+        for (i, c) in assignment.iter().enumerate() {
+            groups[*c].push(i);
+        }
+        groups.retain(|g| !g.is_empty());
+        groups.sort_by_key(|g| (std::cmp::Reverse(g.len()), g[0]));
+
+        let words = surface_words(&category.papers);
+        category.topics = groups
+            .into_iter()
+            .map(|members| {
+                let terms = top_terms(&members, &vectors, config.label_terms)
+                    .into_iter()
+                    .map(|t| words.get(&t).cloned().unwrap_or(t))
+                    .collect::<Vec<_>>();
+                ArxivTopic {
+                    label: terms.join(", "),
+                    terms,
+                    papers: members
+                        .iter()
+                        .map(|i| category.papers[*i].clone())
+                        .collect(),
+                }
+            })
+            .collect();
+        clustered += 1;
+    }
+    info!("Clustered {} categories into topics", clustered);
+}
+
+/// Spherical k-means, seeded by farthest-first traversal from the first paper so that the same
+/// papers always give the same topics. There are at most as many clusters as vectors.
+fn kmeans(vectors: &[TermVector], k: usize, iterations: usize) -> Vec<usize> {
+    let Some(first) = vectors.first() else {
+        return Vec::new();
+    };
+    let k = k.clamp(1, vectors.len());
+    let mut centroids = vec![first.clone()];
+    while centroids.len() < k {
+        let closest = |v: &TermVector| {
+            centroids
+                .iter()
+                .map(|c| cosine(v, c))
+                .fold(f64::MIN, f64::max)
+        };
+        let next = (0..vectors.len())
+            .min_by(|a, b| closest(&vectors[*a]).total_cmp(&closest(&vectors[*b])))
+            .unwrap_or(0);
+        centroids.push(vectors[next].clone());
+    }
+
+    let mut assignment = vec![0; vectors.len()];
+    for iteration in 0..iterations.max(1) {
+        let next: Vec<usize> = vectors
+            .iter()
+            .map(|v| {
+                (0..k)
+                    .min_by(|a, b| cosine(v, &centroids[*b]).total_cmp(&cosine(v, &centroids[*a])))
+                    .unwrap_or(0)
+            })
+            .collect();
+        if iteration > 0 && next == assignment {
+            break;
+        }
+        assignment = next;
+
+        centroids = vec![TermVector::new(); k];
+        for (vector, c) in vectors.iter().zip(&assignment) {
+            for (term, weight) in vector {
+                *centroids[*c].entry(term.clone()).or_default() += weight;
+            }
+        }
+        centroids.iter_mut().for_each(normalize);
+    }
+    assignment
+}
+
+/// The heaviest terms of the centroid of `members`.
+fn top_terms(members: &[usize], vectors: &[TermVector], n: usize) -> Vec<String> {
+    let mut centroid = TermVector::new();
+    for i in members {
+        for (term, weight) in &vectors[*i] {
+            *centroid.entry(term.clone()).or_default() += weight;
+        }
+    }
+    let mut terms: Vec<(String, f64)> = centroid.into_iter().collect();
+    terms.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.into_iter().take(n).map(|(t, _)| t).collect()
+}
+
+/// The most frequent word of each stem, to label topics with words rather than stems.
+fn surface_words(papers: &[Arxiv]) -> HashMap<String, String> {
+    let stemmer = Stemmer::create(Algorithm::English);
+    let mut counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for paper in papers {
+        for word in text::content_words(&format!("{} {}", paper.title, paper.summary)) {
+            let stem = stemmer.stem(&word).into_owned();
+            *counts.entry(stem).or_default().entry(word).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter_map(|(stem, words)| {
+            let word = words
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))?
+                .0;
+            Some((stem, word))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ArxivCategory;
+    use chrono::Utc;
+
+    fn paper(id: usize, title: &str) -> Arxiv {
+        let mut paper = Arxiv::new();
+        paper.id = format!("2401.{:05}v1", id);
+        paper.title = title.to_string();
+        paper
+    }
+
+    fn vectors(titles: &[&str]) -> Vec<TermVector> {
+        let terms: Vec<Vec<String>> = titles.iter().map(|t| text::terms(t)).collect();
+        let tfidf = TfIdf::fit(&terms);
+        terms.iter().map(|t| tfidf.vector(t)).collect()
+    }
+
+    fn config(min_papers: usize, papers_per_topic: usize) -> Clustering {
+        Clustering {
+            enabled: true,
+            min_papers,
+            papers_per_topic,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn kmeans_edge_cases() {
+        assert!(kmeans(&[], 3, 10).is_empty());
+        let single = vectors(&["graph networks"]);
+        assert_eq!(kmeans(&single, 3, 10), vec![0]);
+        let two = vectors(&["graph networks", "protein folding"]);
+        let assignment = kmeans(&two, 5, 0);
+        assert_eq!(assignment.len(), 2);
+        assert!(assignment.iter().all(|c| *c < 2));
+        assert_ne!(assignment[0], assignment[1]);
+        assert_eq!(kmeans(&two, 0, 10), vec![0, 0]);
+    }
+
+    #[test]
+    fn kmeans_separates_topics() {
+        let v = vectors(&[
+            "graph neural networks",
+            "protein folding dynamics",
+            "graph networks message passing",
+            "protein structure folding",
+            "neural graph networks",
+        ]);
+        let assignment = kmeans(&v, 2, 20);
+        assert_eq!(assignment, kmeans(&v, 2, 20));
+        assert_eq!(assignment[0], assignment[2]);
+        assert_eq!(assignment[0], assignment[4]);
+        assert_eq!(assignment[1], assignment[3]);
+        assert_ne!(assignment[0], assignment[1]);
+    }
+
+    #[test]
+    fn labels_use_surface_words() {
+        let papers = [paper(1, "Networks of networks"), paper(2, "A network")];
+        let words = surface_words(&papers);
+        assert_eq!(words["network"], "networks");
+        assert!(surface_words(&[]).is_empty());
+
+        let v = vectors(&["graph graph kernels", "graph flows"]);
+        assert_eq!(top_terms(&[0, 1], &v, 1), vec!["graph"]);
+        assert_eq!(top_terms(&[0], &v, 10).len(), 2);
+        assert!(top_terms(&[], &v, 2).is_empty());
+    }
+
+    #[test]
+    fn small_categories_stay_flat() {
+        let category = |papers: Vec<Arxiv>| ArxivCategory {
+            subject: "cs.LG".to_string(),
+            papers,
+            hidden: 0,
+            topics: Vec::new(),
+        };
+        let mut days = vec![ArxivDaily {
+            datetime: Utc::now(),
+            subjects: vec![
+                category(Vec::new()),
+                category(vec![paper(1, "Graph networks")]),
+                category(vec![
+                    paper(2, "Graph neural networks"),
+                    paper(3, "Protein folding"),
+                    paper(4, "Neural networks on graphs"),
+                ]),
+            ],
+        }];
+        cluster(&config(1, 2), &mut days);
+        let subjects = &days[0].subjects;
+        assert!(subjects[0].topics.is_empty());
+        assert!(subjects[1].topics.is_empty());
+        let topics = &subjects[2].topics;
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].papers.len(), 2);
+        assert_eq!(topics[0].terms.len(), 2);
+        assert_eq!(topics[0].label, topics[0].terms.join(", "));
+        assert!(topics[0].terms.contains(&"neural".to_string()));
+
+        days[0].subjects[2].topics.clear();
+        cluster(&config(4, 2), &mut days);
+        assert!(days[0].subjects[2].topics.is_empty());
+    }
+}
@@ -0,0 +1,14 @@
+mod cluster;
+mod mute;
+mod recommend;
+mod related;
+mod score;
+mod tfidf;
+mod watch;
+
+pub use cluster::cluster;
+pub use mute::mute;
+pub use recommend::recommend;
+pub use related::related;
+pub use score::{rule_regex, same_term, score, word_pattern};
+pub use watch::{resolve_authors, watch};
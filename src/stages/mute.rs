@@ -0,0 +1,132 @@
+use crate::config::Mute;
+use crate::core::{Arxiv, ArxivDaily, Author, AuthorAliases};
+use crate::utils::{parse_id, read_id_file};
+
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use tracing::info;
+
+/// Remove muted papers, counting them in the `hidden` field of their section. Muted authors are
+/// resolved through `aliases`, like the authors of papers, and must match a name exactly once
+/// normalized: muting `J. Smith` does not mute John Smith unless an alias says so.
+pub fn mute(config: &Mute, aliases: &AuthorAliases, days: &mut [ArxivDaily]) -> Result<()> {
+    let rules = MuteRules::new(config, aliases)?;
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let mut total = 0;
+    for category in days.iter_mut().flat_map(|d| d.subjects.iter_mut()) {
+        let before = category.papers.len();
+        category.papers.retain(|p| !rules.is_muted(p));
+        category.hidden += before - category.papers.len();
+        total += before - category.papers.len();
+    }
+    info!("Muted {} papers!", total);
+    Ok(())
+}
+
+struct MuteRules {
+    ids: HashSet<String>,
+    /// Normalized canonical names.
+    authors: HashSet<String>,
+    title: Vec<Regex>,
+    summary: Vec<Regex>,
+    comment: Vec<Regex>,
+}
+
+impl MuteRules {
+    fn new(config: &Mute, aliases: &AuthorAliases) -> Result<MuteRules> {
+        let mut ids: HashSet<String> = config
+            .ids
+            .iter()
+            .map(|id| parse_id(id).unwrap_or_else(|| id.trim().to_string()))
+            .collect();
+        if let Some(ids_file) = &config.ids_file {
+            ids.extend(read_id_file(ids_file)?);
+        }
+        let patterns = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Ok(RegexBuilder::new(p).case_insensitive(true).build()?))
+                .collect()
+        };
+        Ok(MuteRules {
+            ids,
+            authors: config
+                .authors
+                .iter()
+                .map(|name| {
+                    let mut author = Author::new(name, None);
+                    aliases.resolve(&mut author);
+                    author.normalized
+                })
+                .collect(),
+            title: patterns(&config.title)?,
+            summary: patterns(&config.summary)?,
+            comment: patterns(&config.comment)?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+            && self.authors.is_empty()
+            && self.title.is_empty()
+            && self.summary.is_empty()
+            && self.comment.is_empty()
+    }
+
+    fn is_muted(&self, paper: &Arxiv) -> bool {
+        let comment = paper.comment.as_deref().unwrap_or_default();
+        self.ids.contains(paper.versionless_id())
+            || self.ids.contains(paper.versioned_id())
+            || paper
+                .authors
+                .iter()
+                .any(|a| self.authors.contains(&a.normalized))
+            || self.title.iter().any(|re| re.is_match(&paper.title))
+            || self.summary.iter().any(|re| re.is_match(&paper.summary))
+            || self.comment.iter().any(|re| re.is_match(comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn paper(authors: &[&str], aliases: &AuthorAliases) -> Arxiv {
+        let mut paper = Arxiv::new();
+        paper.id = "2401.00001v1".to_string();
+        paper.authors = authors
+            .iter()
+            .map(|name| {
+                let mut author = Author::new(name, None);
+                aliases.resolve(&mut author);
+                author
+            })
+            .collect();
+        paper
+    }
+
+    #[test]
+    fn authors_match_exactly() {
+        let aliases = AuthorAliases::new(&HashMap::from([(
+            "Alice Mill".to_string(),
+            vec!["A. Mill".to_string()],
+        )]));
+        let config = Mute {
+            authors: vec!["J. Smith".to_string(), "Mill, A.".to_string()],
+            ..Default::default()
+        };
+        let rules = MuteRules::new(&config, &aliases).unwrap();
+        assert!(rules.is_muted(&paper(&["Ann Lee", "Smith, J."], &aliases)));
+        assert!(!rules.is_muted(&paper(&["John Smith"], &aliases)));
+        assert!(!rules.is_muted(&paper(&["Jane Smith"], &aliases)));
+        assert!(rules.is_muted(&paper(&["Alice  Mill"], &aliases)));
+        assert!(rules.is_muted(&paper(&["A. Mill"], &aliases)));
+        assert!(!rules.is_muted(&paper(&["Anna Mill"], &aliases)));
+        assert!(!rules.is_muted(&paper(&[], &aliases)));
+    }
+}
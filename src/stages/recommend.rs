@@ -0,0 +1,79 @@
+use super::tfidf::{cosine, normalize, paper_terms, TermVector, TfIdf};
+use crate::config::Recommend;
+use crate::core::{Arxiv, ArxivCategory, ArxivDaily};
+
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+/// Score every paper against a term profile of the liked `seeds` and pin the best of each day
+/// in a section of their own, below the watched authors section `after` if any.
+pub fn recommend(config: &Recommend, seeds: &[Arxiv], after: &str, days: &mut [ArxivDaily]) {
+    if seeds.is_empty() {
+        return;
+    }
+
+    let seed_ids: HashSet<&str> = seeds.iter().map(Arxiv::versionless_id).collect();
+    let mut terms: HashMap<String, Vec<String>> = HashMap::new();
+    for paper in days
+        .iter()
+        .flat_map(|d| &d.subjects)
+        .flat_map(|s| &s.papers)
+        .chain(seeds)
+    {
+        terms
+            .entry(paper.versionless_id().to_string())
+            .or_insert_with(|| paper_terms(paper));
+    }
+    let tfidf = TfIdf::fit(terms.values());
+
+    let mut profile = TermVector::new();
+    for seed in seeds {
+        for (term, weight) in tfidf.vector(&terms[seed.versionless_id()]) {
+            *profile.entry(term).or_default() += weight;
+        }
+    }
+    normalize(&mut profile);
+
+    let mut recommended = 0;
+    for day in days.iter_mut() {
+        let mut best: Vec<Arxiv> = Vec::new();
+        for paper in day.subjects.iter_mut().flat_map(|s| &mut s.papers) {
+            let id = paper.versionless_id().to_string();
+            let affinity = cosine(&profile, &tfidf.vector(&terms[&id]));
+            paper.affinity = Some((affinity * 1000.0).round() / 1000.0);
+            if affinity >= config.min_similarity
+                && !seed_ids.contains(id.as_str())
+                && !best.iter().any(|p| p.versionless_id() == id)
+            {
+                best.push(paper.clone());
+            }
+        }
+        best.sort_by(|a, b| {
+            b.affinity
+                .unwrap_or(0.0)
+                .total_cmp(&a.affinity.unwrap_or(0.0))
+        });
+        best.truncate(config.top_k);
+
+        if !best.is_empty() {
+            recommended += best.len();
+            let at = day
+                .subjects
+                .iter()
+                .take_while(|s| s.subject == after)
+                .count();
+            let category = ArxivCategory {
+                subject: config.title.clone(),
+                papers: best,
+                hidden: 0,
+                topics: Vec::new(),
+            };
+            day.subjects.insert(at, category);
+        }
+    }
+    info!(
+        "Recommended {} papers from {} liked papers",
+        recommended,
+        seeds.len()
+    );
+}
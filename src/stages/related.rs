@@ -0,0 +1,178 @@
+use super::tfidf::{paper_terms, TermVector, TfIdf};
+use crate::config::Related;
+use crate::core::{ArxivDaily, RelatedPaper};
+
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Link each paper to the `top_k` most similar papers of every day, by cosine similarity of
+/// their TF-IDF vectors. Versions of the same paper are indexed once.
+pub fn related(config: &Related, days: &mut [ArxivDaily]) {
+    if !config.enabled || config.top_k == 0 {
+        return;
+    }
+
+    let mut papers: IndexMap<String, (RelatedPaper, Vec<String>)> = IndexMap::new();
+    for paper in days
+        .iter()
+        .flat_map(|d| &d.subjects)
+        .flat_map(|s| &s.papers)
+    {
+        papers
+            .entry(paper.versionless_id().to_string())
+            .or_insert_with(|| {
+                let link = RelatedPaper {
+                    id: paper.id.clone(),
+                    title: paper.title.clone(),
+                    updated: paper.updated,
+                    similarity: 0.0,
+                };
+                (link, paper_terms(paper))
+            });
+    }
+    let tfidf = TfIdf::fit(papers.values().map(|(_, terms)| terms));
+    let vectors: Vec<TermVector> = papers
+        .values()
+        .map(|(_, terms)| tfidf.vector(terms))
+        .collect();
+
+    let mut postings: HashMap<&str, Vec<(usize, f64)>> = HashMap::new();
+    for (i, vector) in vectors.iter().enumerate() {
+        for (term, weight) in vector {
+            postings.entry(term).or_default().push((i, *weight));
+        }
+    }
+
+    let mut related: HashMap<&str, Vec<RelatedPaper>> = HashMap::new();
+    for (i, vector) in vectors.iter().enumerate() {
+        let mut similarities: HashMap<usize, f64> = HashMap::new();
+        for (term, weight) in vector {
+            for (j, other) in &postings[term.as_str()] {
+                if *j != i {
+                    *similarities.entry(*j).or_default() += weight * other;
+                }
+            }
+        }
+        let mut best: Vec<(usize, f64)> = similarities
+            .into_iter()
+            .filter(|(_, s)| *s >= config.min_similarity)
+            .collect();
+        best.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        best.truncate(config.top_k);
+
+        let links = best
+            .into_iter()
+            .map(|(j, similarity)| RelatedPaper {
+                similarity: (similarity * 1000.0).round() / 1000.0,
+                ..papers[j].0.clone()
+            })
+            .collect();
+        related.insert(papers.get_index(i).unwrap().0, links);
+    }
+
+    let linked = related.values().filter(|links| !links.is_empty()).count();
+    for paper in days
+        .iter_mut()
+        .flat_map(|d| &mut d.subjects)
+        .flat_map(|s| &mut s.papers)
+    {
+        if let Some(links) = related.get(paper.versionless_id()) {
+            paper.related = links.clone();
+        }
+    }
+    info!(
+        "Related papers: {} of {} papers linked",
+        linked,
+        papers.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Arxiv, ArxivCategory};
+    use chrono::Utc;
+
+    fn day(papers: &[(&str, &str)]) -> ArxivDaily {
+        let papers = papers
+            .iter()
+            .map(|(id, title)| {
+                let mut paper = Arxiv::new();
+                paper.id = id.to_string();
+                paper.title = title.to_string();
+                paper
+            })
+            .collect();
+        ArxivDaily {
+            datetime: Utc::now(),
+            subjects: vec![ArxivCategory {
+                subject: "cs.LG".to_string(),
+                papers,
+                hidden: 0,
+                topics: Vec::new(),
+            }],
+        }
+    }
+
+    fn config(top_k: usize, min_similarity: f64) -> Related {
+        Related {
+            enabled: true,
+            top_k,
+            min_similarity,
+        }
+    }
+
+    fn links(days: &[ArxivDaily], index: usize) -> Vec<&str> {
+        days[0].subjects[0].papers[index]
+            .related
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn no_papers_or_a_single_paper() {
+        let mut days = vec![day(&[])];
+        related(&config(5, 0.0), &mut days);
+        let mut days = vec![day(&[("2401.00001v1", "Graph neural networks")])];
+        related(&config(5, 0.0), &mut days);
+        assert!(links(&days, 0).is_empty());
+    }
+
+    #[test]
+    fn top_k_larger_than_the_collection() {
+        let mut days = vec![day(&[
+            ("2401.00001v1", "Graph neural networks"),
+            ("2401.00002v1", "Graph neural kernels"),
+            ("2401.00003v1", "Neural networks for graphs"),
+        ])];
+        related(&config(10, 0.0), &mut days);
+        assert_eq!(links(&days, 0).len(), 2);
+        assert!(!links(&days, 0).contains(&"2401.00001v1"));
+        let similarity = &days[0].subjects[0].papers[0].related;
+        assert!(similarity[0].similarity >= similarity[1].similarity);
+    }
+
+    #[test]
+    fn versions_and_threshold() {
+        let mut days = vec![
+            day(&[
+                ("2401.00001v2", "Graph neural networks"),
+                ("2401.00002v1", "Protein folding"),
+                ("2401.00003v1", "Graph neural kernels"),
+            ]),
+            day(&[("2401.00001v1", "Graph neural networks")]),
+        ];
+        related(&config(5, 0.1), &mut days);
+        // Both versions are one paper, never related to itself.
+        assert_eq!(links(&days, 0), vec!["2401.00003v1"]);
+        assert_eq!(links(&days, 1), Vec::<&str>::new());
+        let old_version: Vec<&str> = days[1].subjects[0].papers[0]
+            .related
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect();
+        assert_eq!(old_version, vec!["2401.00003v1"]);
+    }
+}
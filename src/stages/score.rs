@@ -0,0 +1,217 @@
+use crate::config::{Field, ScoreRule, Scoring};
+use crate::core::{Arxiv, ArxivDaily};
+
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+use tracing::info;
+
+/// A compiled scoring rule.
+struct Rule {
+    regex: Regex,
+    weight: f64,
+    fields: Vec<Field>,
+}
+
+/// Score every paper with the configured rules, then apply the threshold, which spares papers
+/// by watched authors, and ordering.
+pub fn score(config: &Scoring, days: &mut [ArxivDaily]) -> Result<()> {
+    if config.rules.is_empty() {
+        return Ok(());
+    }
+    info!("Scoring papers with {} rules!", config.rules.len());
+    let rules = compile(config)?;
+
+    for category in days.iter_mut().flat_map(|d| d.subjects.iter_mut()) {
+        category
+            .papers
+            .iter_mut()
+            .for_each(|p| score_paper(&rules, p));
+        if let Some(min_score) = config.min_score {
+            category
+                .papers
+                .retain(|p| !p.watched.is_empty() || p.score.unwrap_or(0.0) >= min_score);
+        }
+        if config.sort {
+            category.sort_by_score();
+        }
+    }
+    Ok(())
+}
+
+fn compile(config: &Scoring) -> Result<Vec<Rule>> {
+    config
+        .rules
+        .iter()
+        .map(|rule| {
+            Ok(Rule {
+                regex: rule_regex(rule)?,
+                weight: rule.weight,
+                fields: rule.fields.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The regex of a scoring rule, its pattern as is or a literal on word boundaries.
+pub fn rule_regex(rule: &ScoreRule) -> Result<Regex, regex::Error> {
+    let pattern = if rule.regex {
+        rule.pattern.clone()
+    } else {
+        word_pattern(&rule.pattern)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!rule.case_sensitive)
+        .build()
+}
+
+/// Escape a literal and anchor it on word boundaries where it starts or ends with a word character.
+pub fn word_pattern(literal: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    format!(
+        "{}{}{}",
+        if is_word(literal.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        },
+        regex::escape(literal),
+        if is_word(literal.chars().last()) {
+            r"\b"
+        } else {
+            ""
+        },
+    )
+}
+
+/// Whether two matches are the same term, compared case-insensitively like a `(?i)` rule.
+pub fn same_term(a: &str, b: &str) -> bool {
+    a == b || a.to_lowercase() == b.to_lowercase()
+}
+
+/// A rule adds its weight once for every field it matches.
+fn score_paper(rules: &[Rule], paper: &mut Arxiv) {
+    let authors: Vec<&str> = paper.authors.iter().map(|a| a.canonical.as_str()).collect();
+    let authors = authors.join(", ");
+    let mut score = 0.0;
+    let mut matched_terms: Vec<String> = Vec::new();
+
+    for rule in rules {
+        for field in &rule.fields {
+            let text = match field {
+                Field::Title => &paper.title,
+                Field::Summary => &paper.summary,
+                Field::Authors => &authors,
+                Field::Comment => match &paper.comment {
+                    Some(comment) => comment,
+                    None => continue,
+                },
+            };
+            let mut matched = false;
+            for m in rule.regex.find_iter(text) {
+                matched = true;
+                if !matched_terms.iter().any(|t| same_term(t, m.as_str())) {
+                    matched_terms.push(m.as_str().to_string());
+                }
+            }
+            if matched {
+                score += rule.weight;
+            }
+        }
+    }
+
+    paper.score = Some(score);
+    paper.matched_terms = matched_terms;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str) -> ScoreRule {
+        ScoreRule {
+            pattern: pattern.to_string(),
+            regex: false,
+            case_sensitive: false,
+            weight: 1.0,
+            fields: Field::all(),
+        }
+    }
+
+    fn paper(title: &str) -> Arxiv {
+        let mut paper = Arxiv::new();
+        paper.title = title.to_string();
+        paper
+    }
+
+    #[test]
+    fn word_pattern_anchors_word_ends_only() {
+        assert_eq!(word_pattern("gan"), r"\bgan\b");
+        assert_eq!(word_pattern("C++"), r"\bC\+\+");
+        assert_eq!(word_pattern(".NET"), r"\.NET\b");
+        assert_eq!(word_pattern("-"), r"\-");
+        assert_eq!(word_pattern(""), "");
+    }
+
+    #[test]
+    fn literal_rules_match_whole_words() {
+        let regex = rule_regex(&rule("GAN")).unwrap();
+        assert!(regex.is_match("A gan for images"));
+        assert!(!regex.is_match("Organic chemistry"));
+        let regex = rule_regex(&rule("C++")).unwrap();
+        assert!(regex.is_match("Fast c++ code"));
+    }
+
+    #[test]
+    fn rule_compile() {
+        let mut regex_rule = rule("graph(s)? neural");
+        regex_rule.regex = true;
+        assert!(rule_regex(&regex_rule)
+            .unwrap()
+            .is_match("Graphs Neural nets"));
+        regex_rule.pattern = "(unclosed".to_string();
+        assert!(rule_regex(&regex_rule).is_err());
+        // Literal rules escape their pattern, so they always compile.
+        assert!(rule_regex(&rule("(unclosed")).is_ok());
+
+        let mut sensitive = rule("BERT");
+        sensitive.case_sensitive = true;
+        let regex = rule_regex(&sensitive).unwrap();
+        assert!(regex.is_match("BERT models"));
+        assert!(!regex.is_match("bert models"));
+    }
+
+    #[test]
+    fn matched_terms_fold_non_ascii_case() {
+        let rules = compile(&Scoring {
+            rules: vec![rule("équation")],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut paper = paper("Équation and équation");
+        score_paper(&rules, &mut paper);
+        assert_eq!(paper.matched_terms, vec!["Équation"]);
+        assert!(same_term("ÜBER", "über"));
+        assert!(!same_term("uber", "über"));
+    }
+
+    #[test]
+    fn weight_counts_once_per_field() {
+        let mut title_only = rule("graph");
+        title_only.weight = 2.5;
+        title_only.fields = vec![Field::Title];
+        let rules = compile(&Scoring {
+            rules: vec![title_only, rule("neural")],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut paper = paper("Graph graph neural");
+        paper.summary = "A graph neural network.".to_string();
+        score_paper(&rules, &mut paper);
+        assert_eq!(paper.score, Some(4.5));
+
+        let mut empty = Arxiv::new();
+        score_paper(&rules, &mut empty);
+        assert_eq!(empty.score, Some(0.0));
+        assert!(empty.matched_terms.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::core::Arxiv;
+use crate::text;
+
+use std::collections::{BTreeMap, HashMap};
+
+/// A sparse term vector, ordered so that sums over it are reproducible.
+pub type TermVector = BTreeMap<String, f64>;
+
+/// Inverse document frequencies of a corpus.
+pub struct TfIdf {
+    idf: HashMap<String, f64>,
+    default_idf: f64,
+}
+
+impl TfIdf {
+    pub fn fit<'a>(docs: impl IntoIterator<Item = &'a Vec<String>>) -> TfIdf {
+        let mut df: HashMap<&str, usize> = HashMap::new();
+        let mut n = 0;
+        for doc in docs {
+            n += 1;
+            let mut seen: Vec<&str> = doc.iter().map(String::as_str).collect();
+            seen.sort_unstable();
+            seen.dedup();
+            for term in seen {
+                *df.entry(term).or_default() += 1;
+            }
+        }
+        let idf = |df: usize| ((1.0 + n as f64) / (1.0 + df as f64)).ln() + 1.0;
+        TfIdf {
+            idf: df
+                .into_iter()
+                .map(|(t, df)| (t.to_string(), idf(df)))
+                .collect(),
+            default_idf: idf(0),
+        }
+    }
+
+    /// Sublinear TF-IDF weights, L2-normalized so that dot products are cosine similarities.
+    pub fn vector(&self, terms: &[String]) -> TermVector {
+        let mut tf: HashMap<&str, f64> = HashMap::new();
+        for term in terms {
+            *tf.entry(term).or_default() += 1.0;
+        }
+        let mut vector: TermVector = tf
+            .into_iter()
+            .map(|(t, tf)| {
+                let idf = self.idf.get(t).copied().unwrap_or(self.default_idf);
+                (t.to_string(), (1.0 + tf.ln()) * idf)
+            })
+            .collect();
+        normalize(&mut vector);
+        vector
+    }
+}
+
+pub fn normalize(vector: &mut TermVector) {
+    let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        vector.values_mut().for_each(|w| *w /= norm);
+    }
+}
+
+/// Terms of a paper, the title counting twice.
+pub fn paper_terms(paper: &Arxiv) -> Vec<String> {
+    let mut terms = text::terms(&paper.title);
+    terms.extend(terms.clone());
+    terms.extend(text::terms(&paper.summary));
+    terms
+}
+
+pub fn cosine(a: &TermVector, b: &TermVector) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(t, w)| large.get(t).map(|v| w * v))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(text: &str) -> Vec<String> {
+        text::terms(text)
+    }
+
+    #[test]
+    fn empty_corpus_and_documents() {
+        let tfidf = TfIdf::fit(std::iter::empty());
+        assert!(tfidf.vector(&[]).is_empty());
+        let vector = tfidf.vector(&doc("graph networks"));
+        assert_eq!(vector.len(), 2);
+        assert!(vector.values().all(|w| w.is_finite() && *w > 0.0));
+
+        let mut zero = TermVector::new();
+        zero.insert("graph".to_string(), 0.0);
+        normalize(&mut zero);
+        assert_eq!(zero["graph"], 0.0);
+        assert_eq!(cosine(&TermVector::new(), &vector), 0.0);
+    }
+
+    #[test]
+    fn vectors_are_unit_length() {
+        let docs = [doc("graph neural networks"), doc("graph graph kernels")];
+        let tfidf = TfIdf::fit(&docs);
+        for d in &docs {
+            let vector = tfidf.vector(d);
+            let norm: f64 = vector.values().map(|w| w * w).sum();
+            assert!((norm - 1.0).abs() < 1e-9);
+            assert!((cosine(&vector, &vector) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rare_terms_weigh_more() {
+        let docs = [
+            doc("graph kernels"),
+            doc("graph networks"),
+            doc("graph flows"),
+        ];
+        let tfidf = TfIdf::fit(&docs);
+        let vector = tfidf.vector(&docs[0]);
+        assert!(vector["kernel"] > vector["graph"]);
+        // Terms unseen at fit time get the highest weight.
+        let unseen = tfidf.vector(&doc("graph sheaves"));
+        assert!(unseen["sheav"] > unseen["graph"]);
+    }
+
+    #[test]
+    fn cosine_is_symmetric() {
+        let docs = [
+            doc("graph neural networks"),
+            doc("neural networks for vision"),
+            doc("protein folding"),
+        ];
+        let tfidf = TfIdf::fit(&docs);
+        let v: Vec<TermVector> = docs.iter().map(|d| tfidf.vector(d)).collect();
+        assert!((cosine(&v[0], &v[1]) - cosine(&v[1], &v[0])).abs() < 1e-12);
+        assert!(cosine(&v[0], &v[1]) > 0.0 && cosine(&v[0], &v[1]) < 1.0);
+        assert_eq!(cosine(&v[0], &v[2]), 0.0);
+    }
+
+    #[test]
+    fn title_counts_twice() {
+        let mut paper = Arxiv::new();
+        paper.title = "Graphs".to_string();
+        paper.summary = "Graphs and kernels.".to_string();
+        let terms = paper_terms(&paper);
+        assert_eq!(terms.iter().filter(|t| *t == "graph").count(), 3);
+        assert_eq!(terms.iter().filter(|t| *t == "kernel").count(), 1);
+        assert!(paper_terms(&Arxiv::new()).is_empty());
+    }
+}
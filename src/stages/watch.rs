@@ -0,0 +1,82 @@
+use crate::config::WatchedAuthor;
+use crate::core::{Arxiv, ArxivCategory, ArxivDaily, Author, AuthorAliases};
+
+use indexmap::IndexMap;
+use tracing::info;
+
+/// Resolve every author of every paper to its canonical name.
+pub fn resolve_authors(aliases: &AuthorAliases, days: &mut [ArxivDaily]) {
+    days.iter_mut()
+        .flat_map(|d| d.subjects.iter_mut())
+        .flat_map(|s| s.papers.iter_mut())
+        .flat_map(|p| p.authors.iter_mut())
+        .for_each(|a| aliases.resolve(a));
+}
+
+/// Flag papers by watched authors and pin them in a `title` section on top of each day.
+pub fn watch(
+    authors: &[WatchedAuthor],
+    aliases: &AuthorAliases,
+    title: &str,
+    days: &mut [ArxivDaily],
+) {
+    if authors.is_empty() {
+        return;
+    }
+    info!("Matching {} watched authors!", authors.len());
+    let watchlist: Vec<(&str, Vec<Author>)> = authors
+        .iter()
+        .map(|watched| {
+            let names = std::iter::once(&watched.name).chain(&watched.variants);
+            let names = names
+                .map(|name| {
+                    let mut author = Author::new(name, None);
+                    aliases.resolve(&mut author);
+                    author
+                })
+                .collect();
+            (watched.name.as_str(), names)
+        })
+        .collect();
+
+    for day in days.iter_mut() {
+        let mut pinned: IndexMap<String, Arxiv> = IndexMap::new();
+        if let Some(index) = day.subjects.iter().position(|s| s.subject == title) {
+            for mut paper in day.subjects.remove(index).papers {
+                paper.watched = watched_names(&watchlist, &paper);
+                pinned.insert(paper.id.clone(), paper);
+            }
+        }
+
+        for paper in day.subjects.iter_mut().flat_map(|s| s.papers.iter_mut()) {
+            paper.watched = watched_names(&watchlist, paper);
+            if !paper.watched.is_empty() {
+                pinned
+                    .entry(paper.id.clone())
+                    .or_insert_with(|| paper.clone());
+            }
+        }
+
+        if !pinned.is_empty() {
+            let category = ArxivCategory {
+                subject: title.to_string(),
+                papers: pinned.into_values().collect(),
+                hidden: 0,
+                topics: Vec::new(),
+            };
+            day.subjects.insert(0, category);
+        }
+    }
+}
+
+fn watched_names(watchlist: &[(&str, Vec<Author>)], paper: &Arxiv) -> Vec<String> {
+    watchlist
+        .iter()
+        .filter(|(_, names)| {
+            names
+                .iter()
+                .any(|name| paper.authors.iter().any(|author| name.matches(author)))
+        })
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
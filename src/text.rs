@@ -0,0 +1,88 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashSet;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Common English and academic words carrying no topic.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "across", "after", "again", "against", "all", "also", "although", "am",
+    "among", "an", "and", "any", "approach", "are", "as", "at", "based", "be", "because", "been",
+    "before", "being", "between", "both", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "during", "each", "et", "few", "for", "from", "further", "had", "has", "have",
+    "having", "here", "how", "however", "i", "if", "in", "into", "is", "it", "its", "itself",
+    "method", "methods", "more", "most", "moreover", "new", "no", "nor", "not", "novel", "of",
+    "on", "once", "only", "or", "other", "our", "ours", "out", "over", "paper", "propose",
+    "proposed", "results", "same", "show", "shows", "so", "some", "such", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "thus", "to",
+    "too", "under", "until", "up", "use", "used", "using", "very", "via", "was", "we", "were",
+    "what", "when", "where", "whether", "which", "while", "who", "whom", "why", "will", "with",
+    "within", "without", "work", "would", "you", "your",
+];
+
+/// Split text into lowercase alphanumeric tokens.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+/// Reduce an English word to its Snowball stem.
+pub fn stem(word: &str) -> String {
+    Stemmer::create(Algorithm::English)
+        .stem(&word.to_lowercase())
+        .into_owned()
+}
+
+/// Unicode normalization forms.
+pub fn nfc(text: &str) -> String {
+    text.nfc().collect()
+}
+
+pub fn nfd(text: &str) -> String {
+    text.nfd().collect()
+}
+
+pub fn nfkc(text: &str) -> String {
+    text.nfkc().collect()
+}
+
+pub fn nfkd(text: &str) -> String {
+    text.nfkd().collect()
+}
+
+/// Remove diacritics, e.g. `Gödel` becomes `Godel`.
+pub fn strip_accents(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
+
+/// Jaccard similarity of the token sets of two strings.
+pub fn jaccard(a: &str, b: &str) -> f64 {
+    let a: HashSet<String> = tokenize(a).into_iter().collect();
+    let b: HashSet<String> = tokenize(b).into_iter().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// Content words of `text`, lowercase and without stopwords, numbers and single letters.
+pub fn content_words(text: &str) -> Vec<String> {
+    tokenize(&strip_accents(text))
+        .into_iter()
+        .filter(|t| t.chars().count() > 1 && !t.chars().all(|c| c.is_numeric()))
+        .filter(|t| !STOPWORDS.contains(&t.as_str()))
+        .collect()
+}
+
+/// Stemmed content words of `text`.
+pub fn terms(text: &str) -> Vec<String> {
+    let stemmer = Stemmer::create(Algorithm::English);
+    content_words(text)
+        .iter()
+        .map(|t| stemmer.stem(t).into_owned())
+        .collect()
+}
@@ -1,3 +1,7 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::fs;
+
 #[macro_export]
 macro_rules! crate_name {
     () => {
@@ -19,4 +23,150 @@ macro_rules! crate_version {
     };
 }
 
+/// Read a file of arXiv IDs, one per line, `#` starting a comment. Lines may also be written as
+/// `arXiv:2401.00001` or as abstract or PDF URLs; any other line is an error.
+pub fn read_id_file(path: &str) -> Result<Vec<String>> {
+    let text =
+        fs::read_to_string(path).map_err(|err| anyhow!("Cannot read IDs {}: {}", path, err))?;
+    let mut ids = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let id = parse_id(line)
+            .ok_or_else(|| anyhow!("{}:{}: not an arXiv ID: {}", path, number + 1, line))?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// The arXiv ID, with its version if any, of an ID, `arXiv:` ID, or abstract or PDF URL.
+pub fn parse_id(text: &str) -> Option<String> {
+    let pattern = Regex::new(concat!(
+        r"(?i)^(?:arxiv:|(?:https?://)?(?:www\.|export\.)?arxiv\.org/(?:abs|pdf)/)?",
+        r"([a-z-]+(?:\.[a-z]{2})?/\d{7}|\d{4}\.\d{4,5})(v\d+)?",
+        r"(?:\.pdf)?/?$",
+    ))
+    .expect("valid arXiv ID pattern");
+    let captures = pattern.captures(text.trim())?;
+    let version = captures
+        .get(2)
+        .map_or(String::new(), |v| v.as_str().to_lowercase());
+    Some(format!("{}{}", &captures[1], version))
+}
+
+/// A file name for `subject`: its lowercase letters and digits, other runs of characters as `-`,
+/// numbered when already `taken`.
+pub fn unique_slug(subject: &str, taken: &mut Vec<String>) -> String {
+    let mut slug = String::new();
+    for c in subject.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_end_matches('-') {
+        "" => "source".to_string(),
+        slug => slug.to_string(),
+    };
+    let mut unique = slug.clone();
+    let mut n = 1;
+    while taken.contains(&unique) {
+        n += 1;
+        unique = format!("{}-{}", slug, n);
+    }
+    taken.push(unique.clone());
+    unique
+}
 
+/// A short hash of `parts`, the same in every build and version, unlike the one of `std`.
+pub fn stable_hash(parts: &[&str]) -> String {
+    // 64-bit FNV-1a, with a NUL byte between parts.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (i, part) in parts.iter().enumerate() {
+        let separator: &[u8] = if i == 0 { b"" } else { b"\0" };
+        for byte in separator.iter().chain(part.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// The value of the environment variable `var`, if any, e.g. an API key or a password.
+pub fn env_var(var: Option<&str>) -> Result<Option<String>> {
+    match var {
+        Some(var) => {
+            Ok(Some(std::env::var(var).map_err(|_| {
+                anyhow!("Environment variable {} is not set", var)
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_forms() {
+        for text in [
+            "2401.00001",
+            "arXiv:2401.00001",
+            "ARXIV:2401.00001",
+            "https://arxiv.org/abs/2401.00001",
+            "http://export.arxiv.org/abs/2401.00001/",
+            "arxiv.org/pdf/2401.00001.pdf",
+        ] {
+            assert_eq!(parse_id(text).as_deref(), Some("2401.00001"), "{}", text);
+        }
+        assert_eq!(
+            parse_id("https://arxiv.org/pdf/2401.00001V2").as_deref(),
+            Some("2401.00001v2")
+        );
+        assert_eq!(
+            parse_id("arXiv:math.GT/0309136v1").as_deref(),
+            Some("math.GT/0309136v1")
+        );
+        assert_eq!(
+            parse_id("hep-th/9901001").as_deref(),
+            Some("hep-th/9901001")
+        );
+        for text in [
+            "",
+            "2401.1",
+            "https://example.org/abs/2401.00001",
+            "2401.00001 and more",
+            "doi:10.1000/xyz",
+        ] {
+            assert_eq!(parse_id(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn stable_hashes() {
+        assert_eq!(stable_hash(&[]), "cbf29ce484222325");
+        assert_eq!(stable_hash(&["a"]), "af63dc4c8601ec8c");
+        assert_ne!(stable_hash(&["ab", "c"]), stable_hash(&["a", "bc"]));
+        assert_eq!(stable_hash(&["a", "b"]), stable_hash(&["a", "b"]));
+    }
+
+    #[test]
+    fn id_file() {
+        let path =
+            std::env::temp_dir().join(format!("{}-ids-{}.txt", crate_name!(), std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "# liked\n2401.00001\n\narXiv:2401.00002v3  # good\n").unwrap();
+        assert_eq!(
+            read_id_file(path).unwrap(),
+            vec!["2401.00001", "2401.00002v3"]
+        );
+        fs::write(path, "2401.00001\nnot an id\n").unwrap();
+        let err = read_id_file(path).unwrap_err().to_string();
+        assert!(err.ends_with(":2: not an arXiv ID: not an id"), "{}", err);
+        fs::remove_file(path).unwrap();
+    }
+}
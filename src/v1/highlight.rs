@@ -0,0 +1,136 @@
+use super::math::{self, Segment};
+use crate::config::{Highlight, Scoring};
+use crate::stages::{rule_regex, same_term, word_pattern};
+
+use anyhow::Result;
+use handlebars::{
+    html_escape, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext,
+    RenderError,
+};
+use regex::{Regex, RegexBuilder};
+
+/// `{{mark text}}` escapes `text` and wraps the configured terms, plus the `matched_terms` of the
+/// current paper, in `<mark class="...">`. With `math`, LaTeX math is rendered as MathML.
+pub struct MarkHelper {
+    terms: Vec<(Regex, String)>,
+    /// The scoring rules, which found the `matched_terms`.
+    rules: Vec<Regex>,
+    matched_class: Option<String>,
+    math: bool,
+}
+
+impl MarkHelper {
+    pub fn new(config: &Highlight, scoring: &Scoring, math: bool) -> Result<MarkHelper> {
+        let groups = std::iter::once((&config.class, &config.terms)).chain(&config.classes);
+        let mut terms = Vec::new();
+        for (class, group) in groups {
+            for term in group {
+                terms.push((term_regex(term, config.case_sensitive)?, class.clone()));
+            }
+        }
+        let rules = if config.matched_terms {
+            scoring
+                .rules
+                .iter()
+                .map(rule_regex)
+                .collect::<Result<_, _>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(MarkHelper {
+            terms,
+            rules,
+            matched_class: config.matched_terms.then(|| config.class.clone()),
+            math,
+        })
+    }
+
+    /// Spans of the configured terms, and of the rule matches listed in `matched`.
+    fn spans<'a>(&'a self, text: &str, matched: &[&str]) -> Vec<(usize, usize, &'a String)> {
+        let mut spans = Vec::new();
+        for (regex, class) in &self.terms {
+            spans.extend(regex.find_iter(text).map(|m| (m.start(), m.end(), class)));
+        }
+        if let Some(class) = self.matched_class.as_ref().filter(|_| !matched.is_empty()) {
+            for regex in &self.rules {
+                spans.extend(
+                    regex
+                        .find_iter(text)
+                        .filter(|m| matched.iter().any(|t| same_term(t, m.as_str())))
+                        .map(|m| (m.start(), m.end(), class)),
+                );
+            }
+        }
+        spans
+    }
+}
+
+impl HelperDef for MarkHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let text = h
+            .param(0)
+            .and_then(|v| v.value().as_str())
+            .ok_or_else(|| RenderError::new("Param 0 (text) is required for mark helper."))?;
+
+        let terms = match self.matched_class {
+            Some(_) => Some(rc.evaluate(ctx, "matched_terms")?),
+            None => None,
+        };
+        let matched: Vec<&str> = terms
+            .iter()
+            .flat_map(|terms| terms.as_json().as_array().into_iter().flatten())
+            .filter_map(|term| term.as_str())
+            .collect();
+
+        if self.math {
+            for segment in math::segments(text) {
+                match segment {
+                    Segment::Text(text) => out.write(&mark(text, self.spans(text, &matched)))?,
+                    Segment::Math {
+                        source,
+                        tex,
+                        display,
+                    } => out.write(&math::render_math(source, tex, display))?,
+                }
+            }
+        } else {
+            out.write(&mark(text, self.spans(text, &matched)))?;
+        }
+        Ok(())
+    }
+}
+
+fn term_regex(term: &str, case_sensitive: bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&word_pattern(term))
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+/// Wrap spans in `<mark>`, earliest then longest first, skipping spans overlapping a taken one.
+fn mark(text: &str, mut spans: Vec<(usize, usize, &String)>) -> String {
+    spans.sort_by_key(|&(start, end, _)| (start, std::cmp::Reverse(end)));
+
+    let mut marked = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, class) in spans {
+        if start < last || start == end {
+            continue;
+        }
+        marked.push_str(&html_escape(&text[last..start]));
+        marked.push_str(&format!(
+            "<mark class=\"{}\">{}</mark>",
+            html_escape(class),
+            html_escape(&text[start..end])
+        ));
+        last = end;
+    }
+    marked.push_str(&html_escape(&text[last..]));
+    marked
+}
@@ -0,0 +1,875 @@
+use handlebars::{
+    html_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+};
+
+/// A piece of text, either prose or `$...$`, `$$...$$`, `\(...\)` or `\[...\]` math.
+pub enum Segment<'a> {
+    Text(&'a str),
+    Math {
+        /// The math with its delimiters, shown when it cannot be converted.
+        source: &'a str,
+        tex: &'a str,
+        display: bool,
+    },
+}
+
+/// Split text into prose and math. Unclosed delimiters are prose.
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    const DELIMITERS: [(&str, &str, bool); 4] = [
+        ("$$", "$$", true),
+        ("\\[", "\\]", true),
+        ("\\(", "\\)", false),
+        ("$", "$", false),
+    ];
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with("\\$") {
+            i += 2;
+            continue;
+        }
+        let found = DELIMITERS.iter().find_map(|(open, close, display)| {
+            let inner = rest.strip_prefix(open)?;
+            let end = find_closing(inner, close, *open == "$")?;
+            Some((open.len(), end, close.len(), *display))
+        });
+        match found {
+            Some((open, end, close, display)) => {
+                if start < i {
+                    segments.push(Segment::Text(&text[start..i]));
+                }
+                let source = &text[i..i + open + end + close];
+                segments.push(Segment::Math {
+                    source,
+                    tex: &source[open..open + end],
+                    display,
+                });
+                i += source.len();
+                start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if start < text.len() {
+        segments.push(Segment::Text(&text[start..]));
+    }
+    segments
+}
+
+/// Offset of the first unescaped `close` in `text`, after some non-blank math. Like Pandoc,
+/// `strict` math may neither start nor end with a space, nor be followed by a digit, so that
+/// `$5 and $10` stays prose.
+fn find_closing(text: &str, close: &str, strict: bool) -> Option<usize> {
+    if strict && text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if !escaped && text[i..].starts_with(close) {
+            let math = &text[..i];
+            let after = text[i + close.len()..].chars().next();
+            if strict
+                && (math.ends_with(char::is_whitespace)
+                    || after.is_some_and(|c| c.is_ascii_digit()))
+            {
+                return None;
+            }
+            return (!math.trim().is_empty()).then_some(i);
+        }
+        escaped = !escaped && c == '\\';
+    }
+    None
+}
+
+/// Escape prose and convert math to MathML, keeping the escaped source of math that does not
+/// parse.
+pub fn render(text: &str) -> String {
+    segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => html_escape(text),
+            Segment::Math {
+                source,
+                tex,
+                display,
+            } => render_math(source, tex, display),
+        })
+        .collect()
+}
+
+pub fn render_math(source: &str, tex: &str, display: bool) -> String {
+    to_mathml(tex, display).unwrap_or_else(|_| html_escape(source))
+}
+
+/// `{{math text}}` escapes `text` and renders its LaTeX math as MathML.
+pub fn math_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let text = h
+        .param(0)
+        .and_then(|v| v.value().as_str())
+        .ok_or_else(|| RenderError::new("Param 0 (text) is required for math helper."))?;
+    out.write(&render(text))?;
+    Ok(())
+}
+
+/// Convert a LaTeX formula to a MathML `<math>` element, or explain why it cannot be.
+pub fn to_mathml(tex: &str, display: bool) -> Result<String, String> {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+        display,
+    };
+    let (row, _) = parser.row(None, Stop::End)?;
+    Ok(format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"{}><semantics><mrow>{}</mrow>\
+         <annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { " display=\"block\"" } else { "" },
+        row,
+        html_escape(tex.trim())
+    ))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stop {
+    End,
+    Brace,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Plain,
+    Scripted,
+    /// Operators taking their scripts under and over in display math, like `\sum`.
+    Limits,
+}
+
+#[derive(Clone, Copy)]
+enum Variant {
+    Normal,
+    Bold,
+    Italic,
+    BoldItalic,
+    Script,
+    Fraktur,
+    DoubleStruck,
+    SansSerif,
+    Monospace,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Atoms up to `stop`, and the delimiter closing a `\left`.
+    fn row(&mut self, variant: Option<Variant>, stop: Stop) -> Result<(String, String), String> {
+        let mut items: Vec<(String, Kind)> = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None if stop == Stop::End => break,
+                None => return Err("missing closing brace or \\right".to_string()),
+                Some('}') if stop == Stop::Brace => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('}') => return Err("unexpected }".to_string()),
+                Some('&') => return Err("alignments are not supported".to_string()),
+                Some('^' | '_') => {
+                    let (base, kind) = items
+                        .pop()
+                        .unwrap_or(("<mrow></mrow>".to_string(), Kind::Plain));
+                    if kind == Kind::Scripted {
+                        return Err("double script, use braces".to_string());
+                    }
+                    items.push((self.scripts(base, kind, variant)?, Kind::Scripted));
+                }
+                Some('\\') if stop == Stop::Right && self.command_ahead("right") => {
+                    self.pos += "\\right".len();
+                    let close = self.delimiter()?;
+                    let row = items.into_iter().map(|(item, _)| item).collect();
+                    return Ok((row, close));
+                }
+                Some(_) => items.push(self.atom(variant)?),
+            }
+        }
+        Ok((
+            items.into_iter().map(|(item, _)| item).collect(),
+            String::new(),
+        ))
+    }
+
+    fn command_ahead(&self, name: &str) -> bool {
+        let end = self.pos + 1 + name.len();
+        self.chars.len() >= end
+            && self.chars[self.pos + 1..end]
+                .iter()
+                .copied()
+                .eq(name.chars())
+            && !self.chars.get(end).is_some_and(char::is_ascii_alphabetic)
+    }
+
+    /// The `_` and `^` scripts following `base`, in either order.
+    fn scripts(
+        &mut self,
+        base: String,
+        kind: Kind,
+        variant: Option<Variant>,
+    ) -> Result<String, String> {
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            self.skip_spaces();
+            let slot = match self.peek() {
+                Some('_') => &mut sub,
+                Some('^') => &mut sup,
+                _ => break,
+            };
+            if slot.is_some() {
+                return Err("double script, use braces".to_string());
+            }
+            self.pos += 1;
+            *slot = Some(self.argument(variant)?);
+        }
+        let under = kind == Kind::Limits && self.display;
+        Ok(match (sub, sup) {
+            (Some(sub), Some(sup)) if under => {
+                format!("<munderover>{}{}{}</munderover>", base, sub, sup)
+            }
+            (Some(sub), None) if under => format!("<munder>{}{}</munder>", base, sub),
+            (None, Some(sup)) if under => format!("<mover>{}{}</mover>", base, sup),
+            (Some(sub), Some(sup)) => format!("<msubsup>{}{}{}</msubsup>", base, sub, sup),
+            (Some(sub), None) => format!("<msub>{}{}</msub>", base, sub),
+            (None, Some(sup)) => format!("<msup>{}{}</msup>", base, sup),
+            (None, None) => base,
+        })
+    }
+
+    /// A braced group or a single atom, as taken by commands and scripts.
+    fn argument(&mut self, variant: Option<Variant>) -> Result<String, String> {
+        self.skip_spaces();
+        match self.peek() {
+            None => Err("missing argument".to_string()),
+            Some('{') => {
+                self.pos += 1;
+                let (row, _) = self.row(variant, Stop::Brace)?;
+                Ok(format!("<mrow>{}</mrow>", row))
+            }
+            Some('}' | '^' | '_' | '&') => Err("missing argument".to_string()),
+            Some(_) => Ok(self.atom(variant)?.0),
+        }
+    }
+
+    /// The raw text of a braced group, for `\text`.
+    fn raw_argument(&mut self) -> Result<String, String> {
+        self.skip_spaces();
+        if self.peek() != Some('{') {
+            return Err("missing braced argument".to_string());
+        }
+        self.pos += 1;
+        let mut depth = 0;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => return Ok(text),
+                '}' => depth -= 1,
+                _ => {}
+            }
+            text.push(c);
+        }
+        Err("missing closing brace".to_string())
+    }
+
+    fn atom(&mut self, variant: Option<Variant>) -> Result<(String, Kind), String> {
+        let c = self.peek().ok_or("missing atom")?;
+        self.pos += 1;
+        let plain = |item: String| Ok((item, Kind::Plain));
+        match c {
+            '{' => {
+                let (row, _) = self.row(variant, Stop::Brace)?;
+                plain(format!("<mrow>{}</mrow>", row))
+            }
+            '0'..='9' => {
+                let mut number = c.to_string();
+                while let Some(d) = self.peek() {
+                    let decimal = d == '.'
+                        && self
+                            .chars
+                            .get(self.pos + 1)
+                            .is_some_and(char::is_ascii_digit);
+                    if !d.is_ascii_digit() && !decimal {
+                        break;
+                    }
+                    number.push(d);
+                    self.pos += 1;
+                }
+                let number: String = number.chars().map(|d| styled(d, variant)).collect();
+                plain(format!("<mn>{}</mn>", number))
+            }
+            '\\' => self.command(variant),
+            '\'' => plain("<mo>\u{2032}</mo>".to_string()),
+            '~' => plain("<mspace width=\"0.278em\"></mspace>".to_string()),
+            '$' | '#' | '%' => Err(format!("unexpected {}", c)),
+            c if c.is_alphabetic() => plain(identifier(c, variant)),
+            c => plain(format!("<mo>{}</mo>", html_escape(&c.to_string()))),
+        }
+    }
+
+    fn command(&mut self, variant: Option<Variant>) -> Result<(String, Kind), String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            let c = self.peek().ok_or("missing command name")?;
+            self.pos += 1;
+            return symbol_command(c).map(|item| (item, Kind::Plain));
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        let plain = |item: String| Ok((item, Kind::Plain));
+
+        if let Some(letter) = greek(&name) {
+            let upright = letter.is_uppercase() || matches!(variant, Some(Variant::Normal));
+            return plain(if upright {
+                format!("<mi mathvariant=\"normal\">{}</mi>", letter)
+            } else {
+                format!("<mi>{}</mi>", letter)
+            });
+        }
+        if let Some(op) = operator(&name) {
+            return plain(format!("<mo>{}</mo>", op));
+        }
+        if let Some(ident) = identifier_symbol(&name) {
+            return plain(format!("<mi>{}</mi>", ident));
+        }
+        if let Some(op) = large_operator(&name) {
+            return Ok((
+                format!("<mo movablelimits=\"true\">{}</mo>", op),
+                Kind::Limits,
+            ));
+        }
+        if let Some(op) = integral(&name) {
+            return plain(format!("<mo>{}</mo>", op));
+        }
+        if FUNCTIONS.contains(&name.as_str()) {
+            return plain(format!("<mi>{}</mi>", name));
+        }
+        if LIMIT_FUNCTIONS.contains(&name.as_str()) {
+            let op = format!("<mo movablelimits=\"true\" form=\"prefix\">{}</mo>", name);
+            return Ok((op, Kind::Limits));
+        }
+        if let Some(style) = font(&name) {
+            let arg = self.argument(Some(style))?;
+            return plain(arg);
+        }
+        if let Some(width) = space(&name) {
+            return plain(format!("<mspace width=\"{}\"></mspace>", width));
+        }
+        if let Some((accent, under)) = accent(&name) {
+            let arg = self.argument(variant)?;
+            return plain(if under {
+                format!(
+                    "<munder accentunder=\"true\">{}<mo>{}</mo></munder>",
+                    arg, accent
+                )
+            } else {
+                format!("<mover accent=\"true\">{}<mo>{}</mo></mover>", arg, accent)
+            });
+        }
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.argument(variant)?;
+                let den = self.argument(variant)?;
+                plain(format!("<mfrac>{}{}</mfrac>", num, den))
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.argument(variant)?;
+                let k = self.argument(variant)?;
+                plain(format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    n, k
+                ))
+            }
+            "sqrt" => {
+                self.skip_spaces();
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let end = self.bracket_end().ok_or("missing ] in \\sqrt")?;
+                    let index: String = self.chars[self.pos..self.pos + end].iter().collect();
+                    self.pos += end + 1;
+                    let index = to_row(&index, self.display)?;
+                    let arg = self.argument(variant)?;
+                    plain(format!("<mroot>{}<mrow>{}</mrow></mroot>", arg, index))
+                } else {
+                    let arg = self.argument(variant)?;
+                    plain(format!("<msqrt>{}</msqrt>", arg))
+                }
+            }
+            "text" | "textrm" | "textnormal" | "textit" | "textbf" | "textsf" | "texttt"
+            | "mbox" => {
+                let text = self.raw_argument()?;
+                plain(format!("<mtext>{}</mtext>", html_escape(&text)))
+            }
+            "operatorname" => {
+                let name = self.raw_argument()?;
+                plain(format!(
+                    "<mi mathvariant=\"normal\">{}</mi>",
+                    html_escape(name.trim())
+                ))
+            }
+            "left" => {
+                let open = self.delimiter()?;
+                let (row, close) = self.row(variant, Stop::Right)?;
+                plain(format!(
+                    "<mrow><mo fence=\"true\">{}</mo>{}<mo fence=\"true\">{}</mo></mrow>",
+                    open, row, close
+                ))
+            }
+            "right" => Err("\\right without \\left".to_string()),
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl"
+            | "biggr" | "Biggl" | "Biggr" | "middle" => {
+                let delimiter = self.delimiter()?;
+                plain(format!("<mo>{}</mo>", delimiter))
+            }
+            "displaystyle" | "textstyle" | "scriptstyle" | "limits" | "nolimits" => {
+                plain(String::new())
+            }
+            _ => Err(format!("unsupported command \\{}", name)),
+        }
+    }
+
+    /// The offset of the `]` closing an optional argument, skipping nested braces and brackets
+    /// and escaped characters.
+    fn bracket_end(&self) -> Option<usize> {
+        let mut depth = 0;
+        let mut chars = self.chars[self.pos..].iter().enumerate();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '{' | '[' => depth += 1,
+                ']' if depth == 0 => return Some(i),
+                '}' | ']' => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The delimiter after `\left`, `\right` or `\big`, empty for `.`.
+    fn delimiter(&mut self) -> Result<String, String> {
+        self.skip_spaces();
+        let c = self.peek().ok_or("missing delimiter")?;
+        self.pos += 1;
+        match c {
+            '.' => Ok(String::new()),
+            '(' | ')' | '[' | ']' | '|' | '/' => Ok(c.to_string()),
+            '<' => Ok("\u{27E8}".to_string()),
+            '>' => Ok("\u{27E9}".to_string()),
+            '\\' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    if self.peek().is_none() {
+                        return Err("missing delimiter".to_string());
+                    }
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "{" | "lbrace" => Ok("{".to_string()),
+                    "}" | "rbrace" => Ok("}".to_string()),
+                    "|" | "Vert" | "lVert" | "rVert" => Ok("\u{2016}".to_string()),
+                    "vert" | "lvert" | "rvert" => Ok("|".to_string()),
+                    "langle" => Ok("\u{27E8}".to_string()),
+                    "rangle" => Ok("\u{27E9}".to_string()),
+                    "lfloor" => Ok("\u{230A}".to_string()),
+                    "rfloor" => Ok("\u{230B}".to_string()),
+                    "lceil" => Ok("\u{2308}".to_string()),
+                    "rceil" => Ok("\u{2309}".to_string()),
+                    _ => Err(format!("unsupported delimiter \\{}", name)),
+                }
+            }
+            _ => Err(format!("unsupported delimiter {}", c)),
+        }
+    }
+}
+
+fn to_row(tex: &str, display: bool) -> Result<String, String> {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+        display,
+    };
+    Ok(parser.row(None, Stop::End)?.0)
+}
+
+fn identifier(c: char, variant: Option<Variant>) -> String {
+    match variant {
+        Some(Variant::Normal) => format!("<mi mathvariant=\"normal\">{}</mi>", c),
+        _ => format!("<mi>{}</mi>", styled(c, variant)),
+    }
+}
+
+/// The Mathematical Alphanumeric Symbol of `c` in `variant`, e.g. `𝒪` for `\mathcal{O}`.
+fn styled(c: char, variant: Option<Variant>) -> char {
+    let Some(variant) = variant else { return c };
+    let exception = match (variant, c) {
+        (Variant::Italic, 'h') => Some('\u{210E}'),
+        (Variant::Script, 'B') => Some('\u{212C}'),
+        (Variant::Script, 'E') => Some('\u{2130}'),
+        (Variant::Script, 'F') => Some('\u{2131}'),
+        (Variant::Script, 'H') => Some('\u{210B}'),
+        (Variant::Script, 'I') => Some('\u{2110}'),
+        (Variant::Script, 'L') => Some('\u{2112}'),
+        (Variant::Script, 'M') => Some('\u{2133}'),
+        (Variant::Script, 'R') => Some('\u{211B}'),
+        (Variant::Script, 'e') => Some('\u{212F}'),
+        (Variant::Script, 'g') => Some('\u{210A}'),
+        (Variant::Script, 'o') => Some('\u{2134}'),
+        (Variant::Fraktur, 'C') => Some('\u{212D}'),
+        (Variant::Fraktur, 'H') => Some('\u{210C}'),
+        (Variant::Fraktur, 'I') => Some('\u{2111}'),
+        (Variant::Fraktur, 'R') => Some('\u{211C}'),
+        (Variant::Fraktur, 'Z') => Some('\u{2128}'),
+        (Variant::DoubleStruck, 'C') => Some('\u{2102}'),
+        (Variant::DoubleStruck, 'H') => Some('\u{210D}'),
+        (Variant::DoubleStruck, 'N') => Some('\u{2115}'),
+        (Variant::DoubleStruck, 'P') => Some('\u{2119}'),
+        (Variant::DoubleStruck, 'Q') => Some('\u{211A}'),
+        (Variant::DoubleStruck, 'R') => Some('\u{211D}'),
+        (Variant::DoubleStruck, 'Z') => Some('\u{2124}'),
+        _ => None,
+    };
+    if let Some(exception) = exception {
+        return exception;
+    }
+    // First capital, small letter and digit of each alphabet.
+    let (upper, lower, digit) = match variant {
+        Variant::Normal => return c,
+        Variant::Bold => (0x1D400, 0x1D41A, Some(0x1D7CE)),
+        Variant::Italic => (0x1D434, 0x1D44E, None),
+        Variant::BoldItalic => (0x1D468, 0x1D482, None),
+        Variant::Script => (0x1D49C, 0x1D4B6, None),
+        Variant::Fraktur => (0x1D504, 0x1D51E, None),
+        Variant::DoubleStruck => (0x1D538, 0x1D552, Some(0x1D7D8)),
+        Variant::SansSerif => (0x1D5A0, 0x1D5BA, Some(0x1D7E2)),
+        Variant::Monospace => (0x1D670, 0x1D68A, Some(0x1D7F6)),
+    };
+    let code = match c {
+        'A'..='Z' => upper + (c as u32 - 'A' as u32),
+        'a'..='z' => lower + (c as u32 - 'a' as u32),
+        '0'..='9' => match digit {
+            Some(digit) => digit + (c as u32 - '0' as u32),
+            None => return c,
+        },
+        _ => return c,
+    };
+    char::from_u32(code).unwrap_or(c)
+}
+
+fn font(name: &str) -> Option<Variant> {
+    Some(match name {
+        "mathrm" | "rm" | "mathup" => Variant::Normal,
+        "mathbf" | "bf" => Variant::Bold,
+        "mathit" | "it" => Variant::Italic,
+        "boldsymbol" | "bm" | "mathbfit" => Variant::BoldItalic,
+        "mathcal" | "cal" | "mathscr" => Variant::Script,
+        "mathfrak" | "frak" => Variant::Fraktur,
+        "mathbb" | "Bbb" => Variant::DoubleStruck,
+        "mathsf" | "sf" => Variant::SansSerif,
+        "mathtt" | "tt" => Variant::Monospace,
+        _ => return None,
+    })
+}
+
+/// `\` followed by a non-letter.
+fn symbol_command(c: char) -> Result<String, String> {
+    Ok(match c {
+        ',' => "<mspace width=\"0.167em\"></mspace>".to_string(),
+        ':' | '>' => "<mspace width=\"0.222em\"></mspace>".to_string(),
+        ';' => "<mspace width=\"0.278em\"></mspace>".to_string(),
+        ' ' => "<mspace width=\"0.333em\"></mspace>".to_string(),
+        '!' => "<mspace width=\"-0.167em\"></mspace>".to_string(),
+        '{' | '}' | '%' | '$' | '#' | '_' | '&' => {
+            format!("<mo>{}</mo>", html_escape(&c.to_string()))
+        }
+        '|' => "<mo>\u{2016}</mo>".to_string(),
+        '\\' => return Err("line breaks are not supported".to_string()),
+        _ => return Err(format!("unsupported command \\{}", c)),
+    })
+}
+
+fn space(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "quad" => "1em",
+        "qquad" => "2em",
+        "thinspace" => "0.167em",
+        "medspace" => "0.222em",
+        "thickspace" => "0.278em",
+        "enspace" => "0.5em",
+        _ => return None,
+    })
+}
+
+/// The accent of a command and whether it goes under.
+fn accent(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "hat" | "widehat" => ("^", false),
+        "check" | "widecheck" => ("\u{02C7}", false),
+        "tilde" | "widetilde" => ("~", false),
+        "bar" | "overline" => ("\u{00AF}", false),
+        "vec" | "overrightarrow" => ("\u{2192}", false),
+        "overleftarrow" => ("\u{2190}", false),
+        "dot" => ("\u{02D9}", false),
+        "ddot" => ("\u{00A8}", false),
+        "acute" => ("\u{00B4}", false),
+        "grave" => ("`", false),
+        "breve" => ("\u{02D8}", false),
+        "overbrace" => ("\u{23DE}", false),
+        "underline" => ("_", true),
+        "underbrace" => ("\u{23DF}", true),
+        _ => return None,
+    })
+}
+
+const FUNCTIONS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "dim", "exp",
+    "hom", "ker", "lg", "ln", "log", "sec", "sin", "sinh", "tan", "tanh",
+];
+
+const LIMIT_FUNCTIONS: &[&str] = &[
+    "argmax", "argmin", "det", "gcd", "inf", "lim", "liminf", "limsup", "max", "min", "Pr", "sup",
+];
+
+fn greek(name: &str) -> Option<char> {
+    Some(match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+fn identifier_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "infty" => "\u{221E}",
+        "partial" => "\u{2202}",
+        "nabla" => "\u{2207}",
+        "ell" => "\u{2113}",
+        "hbar" => "\u{210F}",
+        "emptyset" | "varnothing" => "\u{2205}",
+        "aleph" => "\u{2135}",
+        "Re" => "\u{211C}",
+        "Im" => "\u{2111}",
+        "wp" => "\u{2118}",
+        _ => return None,
+    })
+}
+
+fn large_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "\u{2211}",
+        "prod" => "\u{220F}",
+        "coprod" => "\u{2210}",
+        "bigcup" => "\u{22C3}",
+        "bigcap" => "\u{22C2}",
+        "bigoplus" => "\u{2A01}",
+        "bigotimes" => "\u{2A02}",
+        "bigvee" => "\u{22C1}",
+        "bigwedge" => "\u{22C0}",
+        _ => return None,
+    })
+}
+
+fn integral(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "int" => "\u{222B}",
+        "iint" => "\u{222C}",
+        "iiint" => "\u{222D}",
+        "oint" => "\u{222E}",
+        _ => return None,
+    })
+}
+
+fn operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "times" => "\u{00D7}",
+        "cdot" => "\u{22C5}",
+        "pm" => "\u{00B1}",
+        "mp" => "\u{2213}",
+        "div" => "\u{00F7}",
+        "ast" => "\u{2217}",
+        "star" => "\u{22C6}",
+        "circ" => "\u{2218}",
+        "bullet" => "\u{2219}",
+        "oplus" => "\u{2295}",
+        "otimes" => "\u{2297}",
+        "odot" => "\u{2299}",
+        "setminus" => "\u{2216}",
+        "cup" => "\u{222A}",
+        "cap" => "\u{2229}",
+        "wedge" | "land" => "\u{2227}",
+        "vee" | "lor" => "\u{2228}",
+        "neg" | "lnot" => "\u{00AC}",
+        "leq" | "le" => "\u{2264}",
+        "geq" | "ge" => "\u{2265}",
+        "leqslant" => "\u{2A7D}",
+        "geqslant" => "\u{2A7E}",
+        "neq" | "ne" => "\u{2260}",
+        "ll" => "\u{226A}",
+        "gg" => "\u{226B}",
+        "approx" => "\u{2248}",
+        "sim" => "\u{223C}",
+        "simeq" => "\u{2243}",
+        "cong" => "\u{2245}",
+        "equiv" => "\u{2261}",
+        "propto" => "\u{221D}",
+        "prec" => "\u{227A}",
+        "succ" => "\u{227B}",
+        "preceq" => "\u{2AAF}",
+        "succeq" => "\u{2AB0}",
+        "in" => "\u{2208}",
+        "notin" => "\u{2209}",
+        "ni" => "\u{220B}",
+        "subset" => "\u{2282}",
+        "supset" => "\u{2283}",
+        "subseteq" => "\u{2286}",
+        "supseteq" => "\u{2287}",
+        "forall" => "\u{2200}",
+        "exists" => "\u{2203}",
+        "nexists" => "\u{2204}",
+        "mid" => "\u{2223}",
+        "nmid" => "\u{2224}",
+        "parallel" => "\u{2225}",
+        "perp" | "bot" => "\u{22A5}",
+        "top" => "\u{22A4}",
+        "vdash" => "\u{22A2}",
+        "models" => "\u{22A8}",
+        "to" | "rightarrow" => "\u{2192}",
+        "leftarrow" | "gets" => "\u{2190}",
+        "leftrightarrow" => "\u{2194}",
+        "Rightarrow" | "implies" => "\u{21D2}",
+        "Leftarrow" => "\u{21D0}",
+        "Leftrightarrow" | "iff" => "\u{21D4}",
+        "mapsto" => "\u{21A6}",
+        "longrightarrow" => "\u{27F6}",
+        "longmapsto" => "\u{27FC}",
+        "uparrow" => "\u{2191}",
+        "downarrow" => "\u{2193}",
+        "ldots" | "dots" | "dotsc" | "dotsb" => "\u{2026}",
+        "cdots" => "\u{22EF}",
+        "vdots" => "\u{22EE}",
+        "ddots" => "\u{22F1}",
+        "langle" => "\u{27E8}",
+        "rangle" => "\u{27E9}",
+        "lfloor" => "\u{230A}",
+        "rfloor" => "\u{230B}",
+        "lceil" => "\u{2308}",
+        "rceil" => "\u{2309}",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "\u{2016}",
+        "colon" => ":",
+        "prime" => "\u{2032}",
+        "dagger" => "\u{2020}",
+        "triangle" => "\u{25B3}",
+        "angle" => "\u{2220}",
+        "degree" => "\u{00B0}",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backslash_delimiter_at_end() {
+        assert_eq!(
+            to_mathml(r"x\big\", false),
+            Err("missing delimiter".to_string())
+        );
+        assert_eq!(
+            to_mathml(r"\left\", false),
+            Err("missing delimiter".to_string())
+        );
+    }
+
+    #[test]
+    fn sqrt_index_with_escaped_bracket() {
+        assert_eq!(render(r"$\sqrt[\big\]1$"), r"$\sqrt[\big\]1$");
+        assert_eq!(render(r"$\sqrt[\left\]{x}$"), r"$\sqrt[\left\]{x}$");
+    }
+
+    #[test]
+    fn sqrt_index_with_nested_brackets() {
+        let mathml = to_mathml(r"\sqrt[{[n]}]{x}", false).unwrap();
+        assert!(mathml.contains("<mroot><mrow><mi>x</mi></mrow><mrow><mrow><mo>[</mo>"));
+        assert!(to_mathml(r"\sqrt[\left[n\right]]{x}", false).is_ok());
+    }
+}
@@ -1,37 +1,73 @@
+mod highlight;
+mod math;
 mod render;
 mod rhai_ext;
+mod sanitize;
+mod script;
+mod script_runner;
 mod utils;
 mod sturcts;
 
+use handlebars::Handlebars;
 use std::fs::File;
 use std::io::Write;
 use tracing::info;
-use render::handlebars;
-use sturcts::ArxivRender;
-use utils::copy_statics_to_target;
-
-use crate::config::Config;
-use crate::core::ArxivCollection;
-
-pub fn main(config: &Config, raw_data: ArxivCollection) -> anyhow::Result<()> {
-    let mut render_data = ArxivRender::new(config.site_title.clone(), raw_data);
-    render_data.sort();
-
-    let hbs = handlebars(config)?;
-    info!("Copying static files!");
-    copy_statics_to_target(config)?;
-    info!("Rendering templates!");
-    let render_result = hbs.render("index", &render_data)?;
-    let target_dir = std::path::Path::new(config.target_dir.as_str());
-    let default_path = config
-        .target_name
-        .as_ref()
-        .map(|s| s.as_str())
-        .unwrap_or_else(|| "index.html");
-    let index_path = target_dir.join(default_path);
-    let mut output_file = File::create(&index_path)?;
-    output_file.write_all(render_result.as_bytes())?;
-    info!("{} generated", index_path.to_string_lossy());
-
-    Ok(())
-}
\ No newline at end of file
+use utils::TEMPLATES_SRC;
+
+pub use script_runner::script_test;
+pub(crate) use math::{segments, Segment};
+pub(crate) use render::{handlebars, with_templates};
+pub(crate) use sturcts::ArxivRender;
+pub(crate) use utils::copy_statics_to_target;
+
+use crate::config::{Config, SiteOutput};
+use crate::output::Renderer;
+
+/// The single page `index.html` with every day.
+pub struct Site {
+    target_dir: String,
+    target_name: String,
+    templates_dir: String,
+}
+
+impl Site {
+    pub fn new(config: &Config, output: &SiteOutput) -> Site {
+        Site {
+            target_dir: output.target_dir.clone().unwrap_or_else(|| config.target_dir.clone()),
+            target_name: output
+                .target_name
+                .clone()
+                .or_else(|| config.target_name.clone())
+                .unwrap_or_else(|| "index.html".to_string()),
+            templates_dir: output
+                .templates_dir
+                .clone()
+                .unwrap_or_else(|| config.templates_dir.clone()),
+        }
+    }
+}
+
+impl Renderer for Site {
+    fn name(&self) -> &str {
+        "v1"
+    }
+
+    fn render(
+        &self,
+        config: &Config,
+        hbs: &Handlebars<'static>,
+        render_data: &ArxivRender,
+    ) -> anyhow::Result<()> {
+        let hbs = with_templates(hbs, &[("index", TEMPLATES_SRC)], &self.templates_dir)?;
+        info!("Copying static files!");
+        copy_statics_to_target(config, &self.target_dir)?;
+        info!("Rendering templates!");
+        let render_result = hbs.render("index", render_data)?;
+        let index_path = std::path::Path::new(&self.target_dir).join(&self.target_name);
+        let mut output_file = File::create(&index_path)?;
+        output_file.write_all(render_result.as_bytes())?;
+        info!("{} generated", index_path.to_string_lossy());
+
+        Ok(())
+    }
+}
@@ -1,40 +1,45 @@
-use crate::core::Arxiv;
 use crate::Config;
-use super::rhai_ext::{RegexPackage, RhaiMatch};
-use super::utils::TEMPLATES_SRC;
+use super::highlight::MarkHelper;
+use super::math::math_helper;
+use super::sanitize::{SafeHelper, Sanitizer};
+use super::script::{load_scripts, script_engine};
 
 use anyhow::Result;
 use chrono::{DateTime, SecondsFormat, Utc};
 use handlebars::Handlebars;
 use handlebars::{no_escape, Context, Helper, Output, RenderContext, RenderError};
-use regex::Regex;
-use rhai::packages::Package;
+use std::sync::Arc;
 use tracing::info;
 
+/// Build the render engine shared by every output: the script engine, with the key-value store
+/// of the build, and the helpers, but no templates.
 pub fn handlebars(config: &Config) -> Result<Handlebars<'static>> {
     info!("Building Script Engine!");
-    let mut engine = rhai::Engine::new();
-    engine
-        .register_type_with_name::<Regex>("Regex")
-        .register_type_with_name::<RhaiMatch>("Match")
-        .register_type_with_name::<Arxiv>("Arxiv");
-
+    let engine = Arc::new(script_engine(&config.script_limits));
     info!("Building Script Engine Done!");
-    engine.register_global_module(RegexPackage::new().as_shared_module());
 
     info!("Building Handlebars Render Engine!");
     let mut handlebars = Handlebars::new();
 
-    handlebars.set_engine(engine);
     handlebars.register_helper("time_format", Box::new(time_format_helper));
+    handlebars.register_helper(
+        "mark",
+        Box::new(MarkHelper::new(
+            &config.highlight,
+            &config.scoring,
+            config.math.enabled,
+        )?),
+    );
+    handlebars.register_helper("math", Box::new(math_helper));
 
-    handlebars.register_escape_fn(no_escape);
-    handlebars.register_template_string("index", TEMPLATES_SRC)?;
-    handlebars.register_templates_directory(".hbs", &config.templates_dir)?;
-
-    for (name, script_path) in &config.scripts {
-        info!("Loading Script: {} => {}", name, script_path);
-        handlebars.register_script_helper_file(name, script_path)?;
+    let sanitizer = Arc::new(Sanitizer::new(&config.html));
+    handlebars.register_helper("safe", Box::new(SafeHelper::new(sanitizer.clone())));
+    if !config.html.escape {
+        handlebars.register_escape_fn(no_escape);
+    }
+    for helper in load_scripts(config, &engine)? {
+        let name = helper.name().to_string();
+        handlebars.register_helper(&name, Box::new(helper.sanitized(sanitizer.clone())));
     }
 
     info!("Building Handlebars Render Engine Done!");
@@ -42,6 +47,21 @@ pub fn handlebars(config: &Config) -> Result<Handlebars<'static>> {
     Ok(handlebars)
 }
 
+/// A copy of the shared engine with the built-in `templates`, which those of `templates_dir`
+/// override. The copy shares the helpers and the script engine.
+pub fn with_templates(
+    handlebars: &Handlebars<'static>,
+    templates: &[(&str, &str)],
+    templates_dir: &str,
+) -> Result<Handlebars<'static>> {
+    let mut handlebars = handlebars.clone();
+    for (name, source) in templates {
+        handlebars.register_template_string(name, source)?;
+    }
+    handlebars.register_templates_directory(".hbs", templates_dir)?;
+    Ok(handlebars)
+}
+
 fn time_format_helper(
     h: &Helper,
     _: &Handlebars,
@@ -59,9 +79,6 @@ fn time_format_helper(
 
     let fmt = h.param(1).and_then(|v| v.value().as_str());
     let rendered = match fmt {
-        None => datetime.to_string(),
-        Some("rfc2822") => datetime.to_rfc2822(),
-        Some("rfc3339") => datetime.to_rfc3339(),
         Some("rfc3339_opts") => {
             let secform = h
                 .hash_get("secform")
@@ -88,8 +105,18 @@ fn time_format_helper(
 
             datetime.to_rfc3339_opts(secform, use_z)
         }
-        Some(fmt) => datetime.format(fmt).to_string(),
+        fmt => format_datetime(&datetime, fmt),
     };
     out.write(&rendered)?;
     Ok(())
 }
+
+/// Format a datetime the way `time_format` does for `None`, `rfc2822`, `rfc3339` and strftime patterns.
+pub(crate) fn format_datetime(datetime: &DateTime<Utc>, fmt: Option<&str>) -> String {
+    match fmt {
+        None => datetime.to_string(),
+        Some("rfc2822") => datetime.to_rfc2822(),
+        Some("rfc3339") => datetime.to_rfc3339(),
+        Some(fmt) => datetime.format(fmt).to_string(),
+    }
+}
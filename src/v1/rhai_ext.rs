@@ -1,7 +1,9 @@
 #![allow(non_snake_case)]
 
 use rhai::plugin::*;
-use rhai::{def_package, packages::StandardPackage};
+use rhai::{def_package, packages::StandardPackage, Array};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
 
 def_package! {
     pub RegexPackage(module) {
@@ -21,7 +23,7 @@ pub struct RhaiMatch {
 mod regex_module {
     use super::RhaiMatch;
     use regex::{Regex, RegexBuilder};
-    use rhai::{Dynamic, EvalAltResult, ImmutableString, NativeCallContext, Position};
+    use rhai::{Dynamic, EvalAltResult, ImmutableString, Position};
 
     #[rhai_fn(name = "Regex", return_raw)]
     pub fn regex_new(re: &str) -> Result<Regex, Box<EvalAltResult>> {
@@ -145,3 +147,238 @@ mod regex_module {
             .collect()
     }
 }
+
+def_package! {
+    pub DatetimePackage(module) {
+        combine_with_exported_module!(module, "datetime", datetime_module);
+    }
+}
+
+def_package! {
+    pub JsonPackage(module) {
+        combine_with_exported_module!(module, "json", json_module);
+    }
+}
+
+def_package! {
+    pub TextPackage(module) {
+        combine_with_exported_module!(module, "text", text_module);
+    }
+}
+
+/// Register the `kv_*` functions, backed by a store that lives as long as the engine and holds at
+/// most `max_size` keys, like any map, unbounded when 0.
+pub fn register_kv_store(engine: &mut Engine, max_size: usize) {
+    type Store = Arc<RwLock<BTreeMap<String, Dynamic>>>;
+    let store: Store = Default::default();
+    let poisoned = |fn_name: &str| runtime_error(fn_name, "the key-value store is poisoned");
+
+    let kv = store.clone();
+    engine.register_fn(
+        "kv_get",
+        move |key: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let kv = kv.read().map_err(|_| poisoned("kv_get"))?;
+            Ok(kv.get(key).cloned().unwrap_or(Dynamic::UNIT))
+        },
+    );
+    let kv = store.clone();
+    engine.register_fn(
+        "kv_set",
+        move |key: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let mut kv = kv.write().map_err(|_| poisoned("kv_set"))?;
+            if max_size > 0 && kv.len() >= max_size && !kv.contains_key(key) {
+                return Err(EvalAltResult::ErrorDataTooLarge(
+                    "Size of the key-value store".to_string(),
+                    Position::NONE,
+                )
+                .into());
+            }
+            kv.insert(key.to_string(), value);
+            Ok(())
+        },
+    );
+    let kv = store.clone();
+    engine.register_fn(
+        "kv_has",
+        move |key: &str| -> Result<bool, Box<EvalAltResult>> {
+            let kv = kv.read().map_err(|_| poisoned("kv_has"))?;
+            Ok(kv.contains_key(key))
+        },
+    );
+    let kv = store.clone();
+    engine.register_fn(
+        "kv_remove",
+        move |key: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let mut kv = kv.write().map_err(|_| poisoned("kv_remove"))?;
+            Ok(kv.remove(key).unwrap_or(Dynamic::UNIT))
+        },
+    );
+    let kv = store;
+    engine.register_fn("kv_keys", move || -> Result<Array, Box<EvalAltResult>> {
+        let kv = kv.read().map_err(|_| poisoned("kv_keys"))?;
+        Ok(kv.keys().map(|k| Dynamic::from(k.clone())).collect())
+    });
+}
+
+fn runtime_error(fn_name: &str, err: impl ToString) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorInFunctionCall(
+        fn_name.to_string(),
+        err.to_string(),
+        "".into(),
+        Position::NONE,
+    )
+    .into()
+}
+
+#[export_module]
+mod datetime_module {
+    use super::runtime_error;
+    use crate::v1::render::format_datetime;
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+    use rhai::{EvalAltResult, ImmutableString, INT};
+
+    fn parse_any(text: &str) -> Result<DateTime<Utc>, String> {
+        DateTime::parse_from_rfc3339(text)
+            .or_else(|_| DateTime::parse_from_rfc2822(text))
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|err| format!("invalid datetime `{}`: {}", text, err))
+    }
+
+    /// Format an RFC 3339 or RFC 2822 datetime like the `time_format` helper does.
+    #[rhai_fn(name = "time_format", return_raw)]
+    pub fn time_format(text: &str) -> Result<ImmutableString, Box<EvalAltResult>> {
+        let datetime = parse_any(text).map_err(|err| runtime_error("time_format", err))?;
+        Ok(format_datetime(&datetime, None).into())
+    }
+
+    #[rhai_fn(name = "time_format", return_raw)]
+    pub fn time_format_with(text: &str, fmt: &str) -> Result<ImmutableString, Box<EvalAltResult>> {
+        let datetime = parse_any(text).map_err(|err| runtime_error("time_format", err))?;
+        Ok(format_datetime(&datetime, Some(fmt)).into())
+    }
+
+    /// Parse `text` with a strftime pattern into an RFC 3339 string, dates are taken as UTC midnight.
+    #[rhai_fn(name = "time_parse", return_raw)]
+    pub fn time_parse(text: &str, fmt: &str) -> Result<ImmutableString, Box<EvalAltResult>> {
+        let datetime = DateTime::parse_from_str(text, fmt)
+            .map(|d| d.with_timezone(&Utc))
+            .or_else(|_| NaiveDateTime::parse_from_str(text, fmt).map(|d| d.and_utc()))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(text, fmt)
+                    .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+            })
+            .map_err(|err| runtime_error("time_parse", err))?;
+        Ok(datetime.to_rfc3339().into())
+    }
+
+    #[rhai_fn(name = "time_now")]
+    pub fn time_now() -> ImmutableString {
+        Utc::now().to_rfc3339().into()
+    }
+
+    /// Seconds since the Unix epoch.
+    #[rhai_fn(name = "timestamp", return_raw)]
+    pub fn timestamp(text: &str) -> Result<INT, Box<EvalAltResult>> {
+        let datetime = parse_any(text).map_err(|err| runtime_error("timestamp", err))?;
+        Ok(datetime.timestamp())
+    }
+
+    /// Whole days from `from` to `to`.
+    #[rhai_fn(name = "days_between", return_raw)]
+    pub fn days_between(from: &str, to: &str) -> Result<INT, Box<EvalAltResult>> {
+        let from = parse_any(from).map_err(|err| runtime_error("days_between", err))?;
+        let to = parse_any(to).map_err(|err| runtime_error("days_between", err))?;
+        Ok((to - from).num_days())
+    }
+}
+
+#[export_module]
+mod json_module {
+    use super::runtime_error;
+    use rhai::serde::{from_dynamic, to_dynamic};
+    use rhai::{Dynamic, EvalAltResult, ImmutableString};
+    use serde_json::Value as Json;
+
+    #[rhai_fn(name = "json_encode", return_raw)]
+    pub fn json_encode(value: Dynamic) -> Result<ImmutableString, Box<EvalAltResult>> {
+        let json: Json = from_dynamic(&value)?;
+        serde_json::to_string(&json)
+            .map(Into::into)
+            .map_err(|err| runtime_error("json_encode", err))
+    }
+
+    #[rhai_fn(name = "json_decode", return_raw)]
+    pub fn json_decode(text: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+        let json: Json =
+            serde_json::from_str(text).map_err(|err| runtime_error("json_decode", err))?;
+        to_dynamic(json)
+    }
+}
+
+#[export_module]
+mod text_module {
+    use crate::text;
+    use rhai::{Array, Dynamic, ImmutableString, FLOAT, INT};
+
+    #[rhai_fn(name = "tokenize")]
+    pub fn tokenize(text: &str) -> Array {
+        text::tokenize(text)
+            .into_iter()
+            .map(Dynamic::from)
+            .collect()
+    }
+
+    #[rhai_fn(name = "stem")]
+    pub fn stem(word: &str) -> ImmutableString {
+        text::stem(word).into()
+    }
+
+    /// Tokenize `text` and stem every token.
+    #[rhai_fn(name = "stems")]
+    pub fn stems(text: &str) -> Array {
+        text::tokenize(text)
+            .iter()
+            .map(|t| Dynamic::from(text::stem(t)))
+            .collect()
+    }
+
+    #[rhai_fn(name = "levenshtein")]
+    pub fn levenshtein(a: &str, b: &str) -> INT {
+        strsim::levenshtein(a, b) as INT
+    }
+
+    #[rhai_fn(name = "normalized_levenshtein")]
+    pub fn normalized_levenshtein(a: &str, b: &str) -> FLOAT {
+        strsim::normalized_levenshtein(a, b) as FLOAT
+    }
+
+    #[rhai_fn(name = "jaccard")]
+    pub fn jaccard(a: &str, b: &str) -> FLOAT {
+        text::jaccard(a, b) as FLOAT
+    }
+
+    #[rhai_fn(name = "nfc")]
+    pub fn nfc(text: &str) -> ImmutableString {
+        text::nfc(text).into()
+    }
+
+    #[rhai_fn(name = "nfd")]
+    pub fn nfd(text: &str) -> ImmutableString {
+        text::nfd(text).into()
+    }
+
+    #[rhai_fn(name = "nfkc")]
+    pub fn nfkc(text: &str) -> ImmutableString {
+        text::nfkc(text).into()
+    }
+
+    #[rhai_fn(name = "nfkd")]
+    pub fn nfkd(text: &str) -> ImmutableString {
+        text::nfkd(text).into()
+    }
+
+    #[rhai_fn(name = "strip_accents")]
+    pub fn strip_accents(text: &str) -> ImmutableString {
+        text::strip_accents(text).into()
+    }
+}
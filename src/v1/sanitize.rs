@@ -0,0 +1,58 @@
+use crate::config::Html;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+};
+use std::sync::Arc;
+
+/// Cleans untrusted HTML with ammonia, keeping the configured tags and attributes.
+pub struct Sanitizer {
+    tags: Vec<String>,
+    attributes: Vec<String>,
+}
+
+impl Sanitizer {
+    pub fn new(config: &Html) -> Sanitizer {
+        Sanitizer {
+            tags: config.allowed_tags.clone(),
+            attributes: config.allowed_attributes.clone(),
+        }
+    }
+
+    pub fn clean(&self, html: &str) -> String {
+        let mut builder = ammonia::Builder::default();
+        builder
+            .add_tags(self.tags.iter().map(String::as_str))
+            .add_generic_attributes(self.attributes.iter().map(String::as_str));
+        builder.clean(html).to_string()
+    }
+}
+
+/// `{{safe html}}` writes `html` unescaped once sanitized, e.g. `{{safe (highlight title)}}`.
+pub struct SafeHelper {
+    sanitizer: Arc<Sanitizer>,
+}
+
+impl SafeHelper {
+    pub fn new(sanitizer: Arc<Sanitizer>) -> SafeHelper {
+        SafeHelper { sanitizer }
+    }
+}
+
+impl HelperDef for SafeHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let html = h
+            .param(0)
+            .map(|v| v.render())
+            .ok_or_else(|| RenderError::new("Param 0 (html) is required for safe helper."))?;
+        out.write(&self.sanitizer.clean(&html))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use super::rhai_ext::{
+    register_kv_store, DatetimePackage, JsonPackage, RegexPackage, RhaiMatch, TextPackage,
+};
+use super::sanitize::Sanitizer;
+use crate::config::{Config, Script, ScriptLimits};
+use crate::core::Arxiv;
+
+use anyhow::{anyhow, Result};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    ScopedJson,
+};
+use regex::Regex;
+use rhai::packages::Package;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Dynamic, Engine, EvalAltResult, Position, Scope, AST};
+use serde_json::Value as Json;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+thread_local! {
+    /// Wall-clock deadline of the script currently running on this thread.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Build the sandboxed Rhai engine shared by every script helper.
+pub fn script_engine(limits: &ScriptLimits) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_type_with_name::<Regex>("Regex")
+        .register_type_with_name::<RhaiMatch>("Match")
+        .register_type_with_name::<Arxiv>("Arxiv");
+    engine.register_global_module(RegexPackage::new().as_shared_module());
+    engine.register_global_module(DatetimePackage::new().as_shared_module());
+    engine.register_global_module(JsonPackage::new().as_shared_module());
+    engine.register_global_module(TextPackage::new().as_shared_module());
+    register_kv_store(&mut engine, limits.max_map_size);
+
+    engine
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_levels)
+        .set_max_expr_depths(limits.max_expr_depth, limits.max_expr_depth)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_array_size)
+        .set_max_map_size(limits.max_map_size);
+
+    // Checking the clock on every operation is too costly, sample it instead.
+    engine.on_progress(|ops| {
+        if ops % 1024 != 0 {
+            return None;
+        }
+        match DEADLINE.with(|d| d.get()) {
+            Some(deadline) if Instant::now() >= deadline => Some(Dynamic::UNIT),
+            _ => None,
+        }
+    });
+    engine
+}
+
+/// Run `f` with a wall-clock deadline for scripts, restoring the outer deadline afterwards.
+pub fn with_deadline<T>(timeout: Duration, f: impl FnOnce() -> T) -> T {
+    let outer = DEADLINE.with(|d| d.replace(Some(Instant::now() + timeout)));
+    let result = f();
+    DEADLINE.with(|d| d.set(outer));
+    result
+}
+
+/// Compile every `.rhai` file of `config.scripts_dir`, named after the file, then the helpers
+/// listed in `config.scripts`, which take precedence.
+pub fn load_scripts(config: &Config, engine: &Arc<Engine>) -> Result<Vec<ScriptHelper>> {
+    let limits = &config.script_limits;
+    let mut helpers: Vec<ScriptHelper> = Vec::new();
+
+    if let Some(scripts_dir) = &config.scripts_dir {
+        let mut paths: Vec<PathBuf> = fs::read_dir(scripts_dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            if name.ends_with("_test") || config.scripts.contains_key(name.as_ref()) {
+                continue;
+            }
+            info!("Loading Script: {} => {}", name, path.to_string_lossy());
+            helpers.push(ScriptHelper::load(&name, &path, engine.clone(), limits)?);
+        }
+    }
+
+    for (name, script) in &config.scripts {
+        let helper = match script {
+            Script::Path(script_path) => {
+                info!("Loading Script: {} => {}", name, script_path);
+                ScriptHelper::load(name, Path::new(script_path), engine.clone(), limits)?
+            }
+            Script::Inline { source } => {
+                info!("Loading Script: {} => <inline>", name);
+                ScriptHelper::inline(name, source, engine.clone(), limits)?
+            }
+        };
+        helpers.push(helper);
+    }
+    Ok(helpers)
+}
+
+/// A Handlebars helper backed by a compiled Rhai script.
+pub struct ScriptHelper {
+    name: String,
+    origin: String,
+    file: Option<PathBuf>,
+    ast: AST,
+    engine: Arc<Engine>,
+    timeout: Duration,
+    sanitizer: Option<Arc<Sanitizer>>,
+}
+
+impl ScriptHelper {
+    pub fn load(
+        name: &str,
+        path: &Path,
+        engine: Arc<Engine>,
+        limits: &ScriptLimits,
+    ) -> Result<ScriptHelper> {
+        let origin = path.to_string_lossy().to_string();
+        let timeout = Duration::from_millis(limits.timeout_ms);
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| anyhow!(describe_error(name, &origin, timeout, *err)))?;
+        Ok(ScriptHelper {
+            name: name.to_string(),
+            origin,
+            file: Some(path.to_path_buf()),
+            ast,
+            engine,
+            timeout,
+            sanitizer: None,
+        })
+    }
+
+    /// Compile a script written inline in the config file.
+    pub fn inline(
+        name: &str,
+        source: &str,
+        engine: Arc<Engine>,
+        limits: &ScriptLimits,
+    ) -> Result<ScriptHelper> {
+        let origin = format!("config.toml [scripts.{}]", name);
+        let timeout = Duration::from_millis(limits.timeout_ms);
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!(describe_error(name, &origin, timeout, err.into())))?;
+        Ok(ScriptHelper {
+            name: name.to_string(),
+            origin,
+            file: None,
+            ast,
+            engine,
+            timeout,
+            sanitizer: None,
+        })
+    }
+
+    /// Run the script with the given `params` and `hash`, bounded by the configured limits.
+    pub fn call(&self, params: &[&Json], hash: &HashMap<String, &Json>) -> Result<Json, String> {
+        let params = to_dynamic(params).map_err(|err| err.to_string())?;
+        let hash = to_dynamic(hash).map_err(|err| err.to_string())?;
+
+        let mut scope = Scope::new();
+        scope.push_dynamic("params", params);
+        scope.push_dynamic("hash", hash);
+
+        let result = with_deadline(self.timeout, || {
+            self.engine
+                .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+        });
+
+        let result = result.map_err(|err| self.describe(*err))?;
+        from_dynamic(&result).map_err(|err| self.describe(*err))
+    }
+
+    /// Sanitize the output written unescaped by `{{{helper ...}}}`.
+    pub fn sanitized(mut self, sanitizer: Arc<Sanitizer>) -> ScriptHelper {
+        self.sanitizer = Some(sanitizer);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Where the script comes from, for messages.
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// The script file, `None` for inline scripts.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    fn describe(&self, err: EvalAltResult) -> String {
+        describe_error(&self.name, &self.origin, self.timeout, err)
+    }
+}
+
+impl HelperDef for ScriptHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let params: Vec<&Json> = h.params().iter().map(|p| p.value()).collect();
+        let hash: HashMap<String, &Json> = h
+            .hash()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.value()))
+            .collect();
+        let result = self.call(&params, &hash).map_err(RenderError::new)?;
+        Ok(ScopedJson::Derived(result))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let rendered = self.call_inner(h, r, ctx, rc)?.render();
+        let rendered = match &self.sanitizer {
+            _ if !rc.is_disable_escape() => r.get_escape_fn()(&rendered),
+            Some(sanitizer) => sanitizer.clean(&rendered),
+            None => rendered,
+        };
+        out.write(&rendered)?;
+        Ok(())
+    }
+}
+
+pub fn describe_error(name: &str, origin: &str, timeout: Duration, err: EvalAltResult) -> String {
+    match err {
+        EvalAltResult::ErrorTerminated(_, pos) => format!(
+            "Script `{}` ({}): timed out after {}ms",
+            name,
+            location(origin, pos),
+            timeout.as_millis()
+        ),
+        err => format!(
+            "Script `{}` ({}): {}",
+            name,
+            location(origin, err.position()),
+            strip_position(err)
+        ),
+    }
+}
+
+fn location(origin: &str, pos: Position) -> String {
+    match (pos.line(), pos.position()) {
+        (Some(line), Some(col)) => format!("{}:{}:{}", origin, line, col),
+        (Some(line), None) => format!("{}:{}", origin, line),
+        _ => origin.to_string(),
+    }
+}
+
+fn strip_position(mut err: EvalAltResult) -> String {
+    err.set_position(Position::NONE);
+    err.to_string()
+}
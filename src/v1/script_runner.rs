@@ -0,0 +1,218 @@
+use super::script::{describe_error, load_scripts, script_engine, with_deadline, ScriptHelper};
+use crate::cli::ScriptTestArgs;
+use crate::config::Config;
+use crate::core::{Arxiv, ArxivCollection};
+
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexMap;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Array, Dynamic, EvalAltResult, Map, Position, Scope};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Exercise every script helper against fixture papers, then run its `*_test.rhai` file if any.
+pub fn script_test(config: &Config, args: &ScriptTestArgs) -> Result<()> {
+    info!("Building Script Engine!");
+    let engine = Arc::new(script_engine(&config.script_limits));
+    let helpers = Arc::new(load_scripts(config, &engine)?);
+    let fixtures = load_fixtures(config, args)?;
+    info!("Loaded {} fixture papers", fixtures.len());
+
+    let mut failures = 0;
+    for helper in helpers.iter() {
+        if args
+            .script
+            .as_deref()
+            .is_some_and(|name| name != helper.name())
+        {
+            continue;
+        }
+        println!("script {} ({})", helper.name(), helper.origin());
+        for paper in &fixtures {
+            let param = match &args.field {
+                Some(field) => paper.get(field).cloned().unwrap_or(Json::Null),
+                None => paper.clone(),
+            };
+            let id = paper.get("id").and_then(Json::as_str).unwrap_or("?");
+            let start = Instant::now();
+            match helper.call(&[&param], &HashMap::new()) {
+                Ok(output) => println!("  ok    {} ({:.2?}): {}", id, start.elapsed(), output),
+                Err(err) => {
+                    failures += 1;
+                    println!("  error {} ({:.2?}): {}", id, start.elapsed(), err);
+                }
+            }
+        }
+
+        if let Some(test_path) = helper.file().map(test_file) {
+            if test_path.exists() {
+                failures += run_test_file(config, &helpers, &fixtures, &test_path)?;
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} script check(s) failed", failures);
+    }
+    println!("all script checks passed");
+    Ok(())
+}
+
+/// `scripts/highlight.rhai` is tested by `scripts/highlight_test.rhai`.
+fn test_file(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}_test.rhai", stem))
+}
+
+/// Fixture papers are either a JSON list of papers or a cache dump.
+fn load_fixtures(config: &Config, args: &ScriptTestArgs) -> Result<Vec<Json>> {
+    let path = match &args.fixtures {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&config.target_dir).join("cache.json"),
+    };
+    let text = fs::read_to_string(&path)
+        .map_err(|err| anyhow!("Cannot read fixtures {}: {}", path.to_string_lossy(), err))?;
+
+    let mut papers: Vec<Arxiv> = match serde_json::from_str(&text) {
+        Ok(papers) => papers,
+        Err(_) => {
+            let collection: ArxivCollection = serde_json::from_str(&text)?;
+            let mut unique = IndexMap::new();
+            for arxiv in collection
+                .into_values()
+                .flat_map(|s| s.into_values())
+                .flatten()
+            {
+                unique.entry(arxiv.id.clone()).or_insert(arxiv);
+            }
+            unique.into_values().collect()
+        }
+    };
+
+    papers.iter_mut().for_each(|paper| paper.embedding = None);
+
+    papers
+        .iter()
+        .take(args.limit.unwrap_or(usize::MAX))
+        .map(|paper| Ok(serde_json::to_value(paper)?))
+        .collect()
+}
+
+/// Run every `test_*` function of a test file, or the whole file when it defines none.
+fn run_test_file(
+    config: &Config,
+    helpers: &Arc<Vec<ScriptHelper>>,
+    fixtures: &[Json],
+    path: &Path,
+) -> Result<usize> {
+    let path_str = path.to_string_lossy().to_string();
+    let timeout = Duration::from_millis(config.script_limits.timeout_ms);
+    let mut engine = script_engine(&config.script_limits);
+    register_test_api(&mut engine, helpers.clone());
+
+    let ast = engine
+        .compile_file(path.to_path_buf())
+        .map_err(|err| anyhow!(describe_error("test", &path_str, timeout, *err)))?;
+    let tests: Vec<String> = ast
+        .iter_functions()
+        .filter(|f| f.name.starts_with("test_") && f.params.is_empty())
+        .map(|f| f.name.to_string())
+        .collect();
+    let fixtures: Array = fixtures
+        .iter()
+        .map(to_dynamic)
+        .collect::<Result<_, _>>()
+        .map_err(|err| anyhow!(err.to_string()))?;
+
+    let mut failures = 0;
+    let mut report =
+        |name: &str, start: Instant, result: Result<Dynamic, Box<EvalAltResult>>| match result {
+            Ok(_) => println!("  pass  {} ({:.2?})", name, start.elapsed()),
+            Err(err) => {
+                failures += 1;
+                let err = describe_error(name, &path_str, timeout, *err);
+                println!("  fail  {} ({:.2?}): {}", name, start.elapsed(), err);
+            }
+        };
+
+    if tests.is_empty() {
+        let mut scope = Scope::new();
+        scope.push("fixtures", fixtures);
+        let start = Instant::now();
+        let result = with_deadline(timeout, || {
+            engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast)
+        });
+        report(&path_str, start, result);
+    } else {
+        for test in &tests {
+            let mut scope = Scope::new();
+            scope.push("fixtures", fixtures.clone());
+            let start = Instant::now();
+            let result = with_deadline(timeout, || {
+                engine.call_fn::<Dynamic>(&mut scope, &ast, test, ())
+            });
+            report(test, start, result);
+        }
+    }
+    Ok(failures)
+}
+
+/// `helper(name, params[, hash])`, `assert(cond[, msg])` and `assert_eq(left, right[, msg])`.
+fn register_test_api(engine: &mut rhai::Engine, helpers: Arc<Vec<ScriptHelper>>) {
+    let call = move |name: &str, params: Array, hash: Map| -> Result<Dynamic, Box<EvalAltResult>> {
+        let helper = helpers
+            .iter()
+            .find(|h| h.name() == name)
+            .ok_or_else(|| failure(format!("unknown script helper `{}`", name)))?;
+        let params: Vec<Json> = params.iter().map(from_dynamic).collect::<Result<_, _>>()?;
+        let hash: HashMap<String, Json> = hash
+            .iter()
+            .map(|(k, v)| Ok((k.to_string(), from_dynamic(v)?)))
+            .collect::<Result<_, Box<EvalAltResult>>>()?;
+        let params: Vec<&Json> = params.iter().collect();
+        let hash: HashMap<String, &Json> = hash.iter().map(|(k, v)| (k.clone(), v)).collect();
+        let output = helper.call(&params, &hash).map_err(failure)?;
+        to_dynamic(output)
+    };
+    let call = Arc::new(call);
+    let call_with_hash = call.clone();
+    engine.register_fn("helper", move |name: &str, params: Array| {
+        call(name, params, Map::new())
+    });
+    engine.register_fn("helper", move |name: &str, params: Array, hash: Map| {
+        call_with_hash(name, params, hash)
+    });
+
+    engine.register_fn("assert", |cond: bool| check(cond, "assertion failed"));
+    engine.register_fn("assert", |cond: bool, msg: &str| check(cond, msg));
+    engine.register_fn("assert_eq", |left: Dynamic, right: Dynamic| {
+        assert_eq(left, right, "")
+    });
+    engine.register_fn("assert_eq", |left: Dynamic, right: Dynamic, msg: &str| {
+        assert_eq(left, right, msg)
+    });
+}
+
+fn failure(msg: impl ToString) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorRuntime(msg.to_string().into(), Position::NONE).into()
+}
+
+fn check(cond: bool, msg: &str) -> Result<(), Box<EvalAltResult>> {
+    if cond {
+        Ok(())
+    } else {
+        Err(failure(msg))
+    }
+}
+
+fn assert_eq(left: Dynamic, right: Dynamic, msg: &str) -> Result<(), Box<EvalAltResult>> {
+    let l: Json = from_dynamic(&left)?;
+    let r: Json = from_dynamic(&right)?;
+    let msg = format!("assertion failed: `{}` != `{}` {}", l, r, msg);
+    check(l == r, msg.trim_end())
+}
@@ -27,25 +27,25 @@ env!("CARGO_MANIFEST_DIR"),
 "/vendor/system-statics/favicon.ico"
 ));
 
-pub fn copy_statics_to_target(config: &Config) -> Result<()> {
-    fs::create_dir_all(&config.target_dir)?;
+pub fn copy_statics_to_target(config: &Config, target_dir: &str) -> Result<()> {
+    fs::create_dir_all(target_dir)?;
     if Path::new(&config.statics_dir).exists() {
         let mut options = CopyOptions::new();
         options.overwrite = true;
         options.content_only = true;
-        copy(&config.statics_dir, &config.target_dir, &options)?;
+        copy(&config.statics_dir, target_dir, &options)?;
     }
-    let css_path = Path::new(&config.target_dir).join("index.css");
+    let css_path = Path::new(target_dir).join("index.css");
     if !css_path.exists() {
         let mut output_file = File::create(&css_path)?;
         output_file.write_all(STATIC_CSS_SRC.as_bytes())?;
     }
-    let js_path = Path::new(&config.target_dir).join("index.js");
+    let js_path = Path::new(target_dir).join("index.js");
     if !js_path.exists() {
         let mut output_file = File::create(&js_path)?;
         output_file.write_all(STATIC_JS_SRC.as_bytes())?;
     }
-    let ico_path = Path::new(&config.target_dir).join("favicon.ico");
+    let ico_path = Path::new(target_dir).join("favicon.ico");
     if !ico_path.exists() {
         let mut output_file = File::create(&ico_path)?;
         output_file.write_all(STATIC_ICO_SRC)?;
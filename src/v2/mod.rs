@@ -0,0 +1,188 @@
+mod site;
+
+use anyhow::Result;
+use handlebars::{Context, Handlebars, Helper, Output, RenderContext, RenderError};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::config::{Config, SiteOutput};
+use crate::output::Renderer;
+use crate::v1::{copy_statics_to_target, with_templates, ArxivRender};
+use site::{day_url, paper_url, DaysContent, Page, PaperContent};
+
+pub(crate) use site::{paper_slug, Sitemap};
+
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "layout",
+        include_str!("../../vendor/system-templates/v2/layout.hbs"),
+    ),
+    (
+        "article",
+        include_str!("../../vendor/system-templates/v2/article.hbs"),
+    ),
+    (
+        "days",
+        include_str!("../../vendor/system-templates/v2/days.hbs"),
+    ),
+    (
+        "index",
+        include_str!("../../vendor/system-templates/v2/index.hbs"),
+    ),
+    (
+        "day",
+        include_str!("../../vendor/system-templates/v2/day.hbs"),
+    ),
+    (
+        "source",
+        include_str!("../../vendor/system-templates/v2/source.hbs"),
+    ),
+    (
+        "paper",
+        include_str!("../../vendor/system-templates/v2/paper.hbs"),
+    ),
+];
+
+/// The site as pages: the latest days on the index, then a page per day, per source and per
+/// paper, all laid out by the `layout` template.
+///
+/// Its templates are overridden from `templates_dir/v2`, so that the `index` of `v1` never
+/// replaces its own.
+pub struct Site {
+    target_dir: String,
+    target_name: String,
+    templates_dir: String,
+}
+
+impl Site {
+    pub fn new(config: &Config, output: &SiteOutput) -> Site {
+        let subdir = |dir: &str| Path::new(dir).join("v2").to_string_lossy().into_owned();
+        Site {
+            target_dir: output
+                .target_dir
+                .clone()
+                .unwrap_or_else(|| subdir(&config.target_dir)),
+            target_name: output
+                .target_name
+                .clone()
+                .or_else(|| config.target_name.clone())
+                .unwrap_or_else(|| "index.html".to_string()),
+            templates_dir: output
+                .templates_dir
+                .clone()
+                .unwrap_or_else(|| subdir(&config.templates_dir)),
+        }
+    }
+}
+
+impl Renderer for Site {
+    fn name(&self) -> &str {
+        "v2"
+    }
+
+    fn render(
+        &self,
+        config: &Config,
+        hbs: &Handlebars<'static>,
+        render_data: &ArxivRender,
+    ) -> Result<()> {
+        let sitemap = Sitemap::new(render_data);
+
+        let mut hbs = with_templates(hbs, TEMPLATES, &self.templates_dir)?;
+        hbs.register_helper("paper_url", Box::new(paper_url_helper));
+        info!("Copying static files!");
+        copy_statics_to_target(config, &self.target_dir)?;
+        info!("Rendering templates!");
+
+        let target_dir = Path::new(&self.target_dir);
+        let site = &sitemap.site;
+        let index = Page {
+            site,
+            base: "",
+            title: site.site_title.to_string(),
+            content: DaysContent {
+                days: sitemap
+                    .dates
+                    .values()
+                    .take(config.pages.index_days)
+                    .flatten()
+                    .copied()
+                    .collect(),
+            },
+        };
+        write_page(&hbs, "index", &index, &target_dir.join(&self.target_name))?;
+
+        for (date, days) in &sitemap.dates {
+            let page = Page {
+                site,
+                base: "../",
+                title: date.clone(),
+                content: DaysContent { days: days.clone() },
+            };
+            write_page(&hbs, "day", &page, &target_dir.join(day_url(date)))?;
+        }
+
+        for (source, link) in sitemap.sources.values().zip(&site.sources) {
+            let page = Page {
+                site,
+                base: "../",
+                title: source.subject.to_string(),
+                content: source,
+            };
+            write_page(&hbs, "source", &page, &target_dir.join(&link.url))?;
+        }
+
+        for (paper, sources, dates) in sitemap.papers.values() {
+            let page = Page {
+                site,
+                base: "../",
+                title: paper.title.clone(),
+                content: PaperContent {
+                    paper,
+                    sources: sources.iter().map(|&i| &site.sources[i]).collect(),
+                    days: dates.iter().map(|&i| &site.days[i]).collect(),
+                },
+            };
+            write_page(&hbs, "paper", &page, &target_dir.join(paper_url(&paper.id)))?;
+        }
+
+        info!(
+            "{} pages generated in {}",
+            1 + sitemap.dates.len() + sitemap.sources.len() + sitemap.papers.len(),
+            target_dir.to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+fn write_page<T: Serialize>(
+    hbs: &Handlebars,
+    template: &str,
+    page: &Page<T>,
+    path: &Path,
+) -> Result<()> {
+    let rendered = hbs.render(template, page)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, rendered)?;
+    Ok(())
+}
+
+/// `{{paper_url id}}` is the path of the paper's page from the site root.
+fn paper_url_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> Result<(), RenderError> {
+    let id = h
+        .param(0)
+        .and_then(|v| v.value().as_str())
+        .ok_or_else(|| RenderError::new("Param 0 (id) is required for paper_url helper."))?;
+    out.write(&paper_url(id))?;
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use crate::core::{versionless_id, Arxiv, ArxivDaily};
+use crate::utils::unique_slug;
+use crate::v1::ArxivRender;
+
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// What every page shares: the site and links to the day and source pages.
+#[derive(Serialize, Debug)]
+pub struct Site<'a> {
+    pub site_title: &'a str,
+    pub build_time: DateTime<Utc>,
+    pub project_name: &'static str,
+    pub project_version: &'static str,
+    pub project_homepage: &'static str,
+    pub days: Vec<DayLink>,
+    pub sources: Vec<SourceLink>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DayLink {
+    pub date: String,
+    pub datetime: DateTime<Utc>,
+    /// Path of the page from the site root.
+    pub url: String,
+    pub papers: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SourceLink {
+    pub subject: String,
+    pub slug: String,
+    pub url: String,
+    pub papers: usize,
+}
+
+/// A page: the site, the path back to its root and the page's own content.
+#[derive(Serialize, Debug)]
+pub struct Page<'a, T: Serialize> {
+    pub site: &'a Site<'a>,
+    /// Prefix of links to other pages, `""` at the root and `"../"` one directory down.
+    pub base: &'static str,
+    pub title: String,
+    #[serde(flatten)]
+    pub content: T,
+}
+
+/// The papers of one or more days, as grouped by the `V1` page.
+#[derive(Serialize, Debug)]
+pub struct DaysContent<'a> {
+    pub days: Vec<&'a ArxivDaily>,
+}
+
+/// The papers of a source over every day.
+#[derive(Serialize, Debug)]
+pub struct SourceContent<'a> {
+    pub subject: &'a str,
+    pub hidden: usize,
+    pub days: Vec<SourceDay<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SourceDay<'a> {
+    pub date: String,
+    pub datetime: DateTime<Utc>,
+    pub papers: Vec<&'a Arxiv>,
+}
+
+/// A paper with the sources and days it is listed in.
+#[derive(Serialize, Debug)]
+pub struct PaperContent<'a> {
+    pub paper: &'a Arxiv,
+    pub sources: Vec<&'a SourceLink>,
+    pub days: Vec<&'a DayLink>,
+}
+
+/// Every page of the site, grouped from the rendered days, newest first.
+pub struct Sitemap<'a> {
+    pub site: Site<'a>,
+    /// The days of each date.
+    pub dates: IndexMap<String, Vec<&'a ArxivDaily>>,
+    pub sources: IndexMap<&'a str, SourceContent<'a>>,
+    /// Each paper, once, with the indices of its sources and dates.
+    pub papers: IndexMap<&'a str, (&'a Arxiv, Vec<usize>, Vec<usize>)>,
+}
+
+impl<'a> Sitemap<'a> {
+    pub fn new(render: &'a ArxivRender) -> Sitemap<'a> {
+        let mut dates: IndexMap<String, Vec<&ArxivDaily>> = IndexMap::new();
+        for day in &render.days {
+            dates.entry(date(&day.datetime)).or_default().push(day);
+        }
+
+        let mut sources: IndexMap<&str, SourceContent> = IndexMap::new();
+        let mut papers: IndexMap<&str, (&Arxiv, Vec<usize>, Vec<usize>)> = IndexMap::new();
+        for (date_index, (date, days)) in dates.iter().enumerate() {
+            for category in days.iter().flat_map(|d| &d.subjects) {
+                let entry = sources.entry(category.subject.as_str());
+                let source_index = entry.index();
+                let source = entry.or_insert_with(|| SourceContent {
+                    subject: &category.subject,
+                    hidden: 0,
+                    days: Vec::new(),
+                });
+                source.hidden += category.hidden;
+                if source.days.last().is_none_or(|d| &d.date != date) {
+                    source.days.push(SourceDay {
+                        date: date.clone(),
+                        datetime: days[0].datetime,
+                        papers: Vec::new(),
+                    });
+                }
+                let source_day = source.days.last_mut().unwrap();
+                for paper in &category.papers {
+                    source_day.papers.push(paper);
+                    let entry = papers
+                        .entry(versionless_id(&paper.id))
+                        .or_insert_with(|| (paper, Vec::new(), Vec::new()));
+                    if !entry.1.contains(&source_index) {
+                        entry.1.push(source_index);
+                    }
+                    if !entry.2.contains(&date_index) {
+                        entry.2.push(date_index);
+                    }
+                }
+            }
+        }
+
+        // `all` names the feeds of every source, whose slugs match these.
+        let mut slugs = vec!["all".to_string()];
+        let site = Site {
+            site_title: &render.site_title,
+            build_time: render.build_time,
+            project_name: render.project_name,
+            project_version: render.project_version,
+            project_homepage: render.project_homepage,
+            days: dates
+                .iter()
+                .map(|(date, days)| DayLink {
+                    date: date.clone(),
+                    datetime: days[0].datetime,
+                    url: day_url(date),
+                    papers: days
+                        .iter()
+                        .flat_map(|d| &d.subjects)
+                        .flat_map(|s| &s.papers)
+                        .map(|p| versionless_id(&p.id))
+                        .collect::<HashSet<_>>()
+                        .len(),
+                })
+                .collect(),
+            sources: sources
+                .values()
+                .map(|source| {
+                    let slug = unique_slug(source.subject, &mut slugs);
+                    SourceLink {
+                        subject: source.subject.to_string(),
+                        url: format!("sources/{}.html", slug),
+                        slug,
+                        papers: source.days.iter().map(|d| d.papers.len()).sum(),
+                    }
+                })
+                .collect(),
+        };
+
+        Sitemap {
+            site,
+            dates,
+            sources,
+            papers,
+        }
+    }
+}
+
+pub fn date(datetime: &DateTime<Utc>) -> String {
+    datetime.format("%Y-%m-%d").to_string()
+}
+
+pub fn day_url(date: &str) -> String {
+    format!("days/{}.html", date)
+}
+
+/// Path of the permalink page of a paper from its ID or abstract URL, the same for every version.
+pub fn paper_url(id: &str) -> String {
+    format!("papers/{}.html", paper_slug(id))
+}
+
+/// A file name for a paper: its versionless ID, `/` of old-style IDs as `_`.
+pub fn paper_slug(id: &str) -> String {
+    versionless_id(id).replace('/', "_")
+}